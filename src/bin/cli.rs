@@ -9,10 +9,17 @@ use figment::{
     Figment,
     providers::{Env, Format, Serialized, Toml},
 };
+use horned_owl::model::{ArcStr, IRI};
 use hyper_ontology::config::BuildConfig;
-use hyper_ontology::config::{OntologyConfig, Settings};
+use hyper_ontology::config::{
+    AnnotationConfig, AnnotationPreset, EntityOrder, ExpressionSyntax, OntologyConfig,
+    OntologyFormat, RedirectFormat, Settings,
+};
+use hyper_ontology::export::ExportFormat;
+use hyper_ontology::redirects::redirect_rules;
 use hyper_ontology::render_html::ArcOntologyRender;
 use hyper_ontology::render_html::IRIMappedRenderHTML;
+use hyper_ontology::serve::serve;
 use std::fs;
 use std::path::Path;
 
@@ -22,69 +29,47 @@ fn main() -> Result<()> {
     match matches.subcommand() {
         Some(("build", sms)) => {
             let settings = parser_app(Some(&matches))?;
+            if let Some(b) = &settings.build
+                && b.single_page
+                && b.redirects.is_some()
+            {
+                return Err(eyre::eyre!(
+                    "Redirects need entity pages and cannot be written for a single page build"
+                ));
+            }
             let mut or = ArcOntologyRender::new_with_settings(settings)?;
-            let hm = or.render_all_declarations_html()?;
-            let output_dir = &or
+            let output_dir = or
                 .settings
                 .build
                 .clone()
                 .expect("Expected build config")
                 .output;
-            fs::create_dir_all(output_dir).unwrap_or(println!("Folder already exist"));
-            for (k, v) in hm.iter() {
-                match or.prefix_mapping.shrink_iri(k) {
-                    Ok(i) => {
-                        let iri_parts: Vec<String> = i
-                            .to_string()
-                            .split(":")
-                            .map(|s: &str| s.to_string())
-                            .collect();
-                        let prefix_len = iri_parts.len();
-                        if prefix_len == 1 {
-                            fs::write(format!("{}/{}.html", output_dir, &iri_parts[0]), v).unwrap();
-                        }
-                    }
-                    Err(_) => (),
+            let pages = build_site(&mut or, &output_dir, sms.get_flag("Render"))?;
+            if let Some(format) = or.settings.build.as_ref().and_then(|b| b.redirects) {
+                for (name, rules) in redirect_rules(format, &or.settings.base_path(), &pages) {
+                    fs::write(format!("{output_dir}/{name}"), rules)?;
                 }
             }
-            fs::write(
-                format!("{}/index.html", output_dir),
-                or.render_metadata_html(None).unwrap(),
-            )
-            .unwrap();
-            if let Some(sd) = &or.settings.assets {
-                copy_dir_all(sd, format!("{output_dir}/static"))?;
-            }
-
-            if sms.get_flag("Render") {
-                if let Some(im) = &or.settings.import.clone() {
-                    for n in im.iter() {
-                        if let Some(p) = &n.suffix {
-                            fs::create_dir_all(format!("{output_dir}/{p}"))
-                                .unwrap_or(println!("Folder already exist"));
-                            for (k, v) in hm.iter() {
-                                match or.prefix_mapping.shrink_iri(k) {
-                                    Ok(i) => {
-                                        let is = i.to_string();
-                                        if is.contains(p) {
-                                            let iss = is.replace(":", "/");
-                                            fs::write(format!("./{output_dir}/{iss}.html"), v)
-                                                .unwrap();
-                                        }
-                                    }
-                                    Err(_) => (),
-                                }
-                            }
-                            fs::write(
-                                format!("{output_dir}/{p}/index.html"),
-                                or.render_metadata_html(Some(&n)).unwrap(),
-                            )
-                            .unwrap();
-                        }
-                    }
+            if or.settings.language_sites.unwrap_or(false) {
+                for lang in or.settings.languages.clone().unwrap_or_default() {
+                    or.language = Some(lang.clone());
+                    build_site(
+                        &mut or,
+                        &format!("{output_dir}/{lang}"),
+                        sms.get_flag("Render"),
+                    )?;
                 }
             }
         }
+        Some(("serve", sms)) => {
+            let settings = parser_app(Some(&matches))?;
+            let mut or = ArcOntologyRender::new_with_settings(settings)?;
+            serve(
+                &mut or,
+                sms.get_one::<String>("Address")
+                    .expect("Address not defined."),
+            )?;
+        }
         _ => {
             let mut help = cli();
             help.print_help()?;
@@ -94,6 +79,101 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+/// Writes the pages of the ontology, its index, its serializations and the
+/// static assets to `output_dir`. Returns the paths of the entity pages.
+/// Single page builds only write the index and the serializations.
+fn build_site(
+    or: &mut ArcOntologyRender,
+    output_dir: &str,
+    render_imports: bool,
+) -> Result<Vec<String>> {
+    fs::create_dir_all(output_dir)?;
+    for format in ExportFormat::ALL {
+        fs::write(
+            format!("{output_dir}/ontology.{}", format.extension()),
+            or.render_ontology_export(format)?,
+        )?;
+    }
+    if or.settings.build.as_ref().is_some_and(|b| b.single_page) {
+        fs::write(
+            format!("{output_dir}/index.html"),
+            or.render_single_page_html()?,
+        )?;
+        return Ok(vec![]);
+    }
+    fs::write(
+        format!("{output_dir}/entities.json"),
+        or.render_entities_json()?,
+    )?;
+    fs::write(
+        format!("{output_dir}/search.json"),
+        or.render_search_json()?,
+    )?;
+    let hm = or.render_all_declarations_html()?;
+    let mut pages: Vec<String> = vec![];
+    for (k, v) in hm.iter() {
+        if let Some(path) = or.entity_path(k)
+            && !path.contains('/')
+        {
+            fs::write(format!("{output_dir}/{path}.html"), v)?;
+            write_entity_exports(or, k, &format!("{output_dir}/{path}"))?;
+            pages.push(path);
+        }
+    }
+    fs::write(
+        format!("{}/index.html", output_dir),
+        or.render_metadata_html(None).unwrap(),
+    )
+    .unwrap();
+    fs::write(
+        format!("{output_dir}/tree.json"),
+        or.render_tree_json(None)?,
+    )?;
+    if let Some(sd) = &or.settings.assets {
+        copy_dir_all(sd, format!("{output_dir}/static"))?;
+    }
+
+    if render_imports && let Some(im) = &or.settings.import.clone() {
+        for n in im.iter() {
+            if let Some(p) = &n.suffix {
+                fs::create_dir_all(format!("{output_dir}/{p}"))?;
+                for (k, v) in hm.iter() {
+                    if let Some(path) = or.entity_path(k)
+                        && path.contains(p)
+                    {
+                        fs::write(format!("./{output_dir}/{path}.html"), v)?;
+                        write_entity_exports(or, k, &format!("./{output_dir}/{path}"))?;
+                        pages.push(path);
+                    }
+                }
+                fs::write(
+                    format!("{output_dir}/{p}/index.html"),
+                    or.render_metadata_html(Some(n)).unwrap(),
+                )
+                .unwrap();
+                fs::write(
+                    format!("{output_dir}/{p}/tree.json"),
+                    or.render_tree_json(Some(n))?,
+                )?;
+            }
+        }
+    }
+    Ok(pages)
+}
+
+/// Writes the JSON document and serializations of an entity next to its page,
+/// `path` being the page path without extension.
+fn write_entity_exports(or: &mut ArcOntologyRender, iri: &IRI<ArcStr>, path: &str) -> Result<()> {
+    fs::write(format!("{path}.json"), or.render_declaration_iri_json(iri)?)?;
+    for format in ExportFormat::ALL {
+        fs::write(
+            format!("{path}.{}", format.extension()),
+            or.render_declaration_iri_export(iri, format)?,
+        )?;
+    }
+    Ok(())
+}
+
 fn copy_dir_all(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> Result<()> {
     fs::create_dir_all(&dst)?;
 
@@ -142,7 +222,7 @@ pub fn parser_app(m: Option<&ArgMatches>) -> Result<Settings> {
     let fig: Figment = if let Some(matches) = m {
         let imports: Option<Vec<OntologyConfig>> = matches.get_many("Imported").map(|m| {
             m.map(|i: &String| {
-                let content: Vec<String> = i.split(":").map(|x| String::from(x)).collect();
+                let content: Vec<String> = i.split(":").map(String::from).collect();
                 content
             })
             .filter(|c| c.len() == 2)
@@ -150,6 +230,7 @@ pub fn parser_app(m: Option<&ArgMatches>) -> Result<Settings> {
                 iri: c[1].clone(),
                 source: None,
                 suffix: Some(c[0].clone()),
+                format: None,
             })
             .collect()
         });
@@ -170,11 +251,19 @@ pub fn parser_app(m: Option<&ArgMatches>) -> Result<Settings> {
                     .get_one::<String>("Output")
                     .expect("Output folder not defined.")
                     .clone(),
+                single_page: sms.get_flag("SinglePage")
+                    || settings.build.as_ref().is_some_and(|b| b.single_page),
+                redirects: sms
+                    .get_one::<RedirectFormat>("Redirects")
+                    .copied()
+                    .or(settings.build.as_ref().and_then(|b| b.redirects)),
             }
         } else {
             BuildConfig {
                 render: false,
                 output: String::from("./public"),
+                single_page: false,
+                redirects: None,
             }
         };
         let cli_settings = Settings {
@@ -206,6 +295,11 @@ pub fn parser_app(m: Option<&ArgMatches>) -> Result<Settings> {
                 } else {
                     settings.ontology.suffix.clone()
                 },
+                format: if let Some(f) = matches.get_one::<OntologyFormat>("Format") {
+                    Some(*f)
+                } else {
+                    settings.ontology.format
+                },
             },
             import: new_imports,
             templates: if let Some(t) = matches
@@ -222,6 +316,59 @@ pub fn parser_app(m: Option<&ArgMatches>) -> Result<Settings> {
                 settings.assets.clone()
             },
             build: Some(cli_build),
+            strict: if matches.get_flag("Strict") {
+                Some(true)
+            } else {
+                settings.strict
+            },
+            catalog: if let Some(c) = matches.get_one("Catalog").map(|m: &String| String::from(m)) {
+                Some(c)
+            } else {
+                settings.catalog.clone()
+            },
+            cache: if let Some(c) = matches.get_one("Cache").map(|m: &String| String::from(m)) {
+                Some(c)
+            } else {
+                settings.cache.clone()
+            },
+            offline: if matches.get_flag("Offline") {
+                Some(true)
+            } else {
+                settings.offline
+            },
+            syntax: if let Some(s) = matches.get_one::<ExpressionSyntax>("Syntax") {
+                Some(*s)
+            } else {
+                settings.syntax
+            },
+            languages: if let Some(l) = matches.get_many::<String>("Language") {
+                Some(l.cloned().collect())
+            } else {
+                settings.languages.clone()
+            },
+            language_sites: if matches.get_flag("LanguageSites") {
+                Some(true)
+            } else {
+                settings.language_sites
+            },
+            annotations: if let Some(p) = matches.get_one::<AnnotationPreset>("Preset") {
+                Some(AnnotationConfig {
+                    preset: Some(*p),
+                    ..settings.annotations.clone().unwrap_or_default()
+                })
+            } else {
+                settings.annotations.clone()
+            },
+            exclude_deprecated: if matches.get_flag("ExcludeDeprecated") {
+                Some(true)
+            } else {
+                settings.exclude_deprecated
+            },
+            order: if let Some(o) = matches.get_one::<EntityOrder>("Order") {
+                Some(*o)
+            } else {
+                settings.order
+            },
         };
         Figment::new().merge(Serialized::defaults(cli_settings))
     } else {
@@ -247,7 +394,59 @@ fn cli() -> Command {
             Arg::new("Source")
                 .long("source")
                 .action(ArgAction::Set)
-                .help("Source of the ontology file (currently only supports local files)"),
+                .help("Source of the ontology, a local file or an http(s) URL."),
+            Arg::new("Format")
+                .long("format")
+                .short('f')
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(OntologyFormat))
+                .help(
+                    "Format of the ontology source. Detected from the file content if not set, \
+                     with the file extension as a fallback.",
+                ),
+            Arg::new("Strict")
+                .long("strict")
+                .action(ArgAction::SetTrue)
+                .help("Fail on RDF that cannot be fully parsed into OWL instead of ignoring it."),
+            Arg::new("Catalog")
+                .long("catalog")
+                .action(ArgAction::Set)
+                .help("XML catalog mapping ontology IRIs to local files (e.g. catalog-v001.xml)."),
+            Arg::new("Cache").long("cache").action(ArgAction::Set).help(
+                "Directory where downloaded ontologies are cached. (defaults to '.hyppo-cache')",
+            ),
+            Arg::new("Offline")
+                .long("offline")
+                .action(ArgAction::SetTrue)
+                .help("Only use cached copies of remote ontologies."),
+            Arg::new("Syntax")
+                .long("syntax")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(ExpressionSyntax))
+                .help("How class expressions are written on entity pages. (defaults to 'macros')"),
+            Arg::new("Language")
+                .long("language")
+                .short('L')
+                .action(ArgAction::Append)
+                .help("Preferred language for labels and annotations. Can be repeated, most preferred first. (defaults to 'en')"),
+            Arg::new("LanguageSites")
+                .long("language-sites")
+                .action(ArgAction::SetTrue)
+                .help("Also build one site per preferred language, under '/<lang>/'."),
+            Arg::new("Preset")
+                .long("annotation-preset")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(AnnotationPreset))
+                .help("Annotation properties shown as labels, definitions, synonyms... (defaults to 'default')"),
+            Arg::new("ExcludeDeprecated")
+                .long("exclude-deprecated")
+                .action(ArgAction::SetTrue)
+                .help("Leave deprecated entities out of the ontology index."),
+            Arg::new("Order")
+                .long("order")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(EntityOrder))
+                .help("Order of entities in the ontology index. (defaults to 'label')"),
             Arg::new("Suffix")
                 .long("suffix")
                 .action(ArgAction::Set)
@@ -294,6 +493,15 @@ fn cli() -> Command {
                         .short('r')
                         .action(ArgAction::SetTrue)
                         .help("Render Imports."),
+                    Arg::new("SinglePage")
+                        .long("single-page")
+                        .action(ArgAction::SetTrue)
+                        .help("Render everything into a single self-contained index.html."),
+                    Arg::new("Redirects")
+                        .long("redirects")
+                        .action(ArgAction::Set)
+                        .value_parser(clap::value_parser!(RedirectFormat))
+                        .help("Write content negotiation rules for a web server. Not available with --single-page."),
                     Arg::new("Output")
                         .long("output")
                         .short('o')
@@ -302,6 +510,16 @@ fn cli() -> Command {
                         .default_value("./public"),
                 ]),
         )
+        .subcommand(
+            clap::command!("serve")
+                .about("Serve the ontology with content negotiation.")
+                .args([Arg::new("Address")
+                    .long("address")
+                    .short('a')
+                    .action(ArgAction::Set)
+                    .help("Address to listen on.")
+                    .default_value("127.0.0.1:8080")]),
+        )
         .subcommand_help_heading("Commands")
 }
 
@@ -0,0 +1,173 @@
+use eyre::Result;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::reader::Reader;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// IRI to file mappings read from an OASIS XML catalog, as written by
+/// Protégé in `catalog-v001.xml`.
+///
+/// Only the `uri` and `rewriteURI` entries are supported, which are the ones
+/// used to redirect `owl:imports`.
+#[derive(Debug, Default)]
+pub struct Catalog {
+    path: String,
+    uris: HashMap<String, PathBuf>,
+    rewrites: Vec<(String, PathBuf)>,
+}
+
+impl Catalog {
+    pub fn from_file(path: &str) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| eyre::eyre!("Could not read catalog {path}: {e}"))?;
+        let root = Path::new(path)
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        let mut catalog = Catalog {
+            path: path.to_string(),
+            ..Default::default()
+        };
+        let mut reader = Reader::from_str(&content);
+        // Each open element may change the base against which paths resolve.
+        let mut bases: Vec<PathBuf> = vec![root];
+        loop {
+            let event = reader.read_event().map_err(|e| {
+                eyre::eyre!(
+                    "Could not parse catalog {path} at byte {}: {e}",
+                    reader.buffer_position()
+                )
+            })?;
+            match event {
+                Event::Start(e) => {
+                    let base = resolve_base(bases.last().unwrap(), &e)?;
+                    catalog.add_entry(&base, &e)?;
+                    bases.push(base);
+                }
+                Event::Empty(e) => {
+                    let base = resolve_base(bases.last().unwrap(), &e)?;
+                    catalog.add_entry(&base, &e)?;
+                }
+                Event::End(_) => {
+                    bases.pop();
+                }
+                Event::Eof => break,
+                _ => (),
+            }
+        }
+        Ok(catalog)
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Local file for an IRI, if the catalog maps it.
+    pub fn resolve(&self, iri: &str) -> Option<String> {
+        if let Some(p) = self.uris.get(iri) {
+            return Some(p.to_string_lossy().into_owned());
+        }
+        self.rewrites
+            .iter()
+            .filter(|(start, _)| iri.starts_with(start.as_str()))
+            .max_by_key(|(start, _)| start.len())
+            .map(|(start, prefix)| {
+                let mut p = prefix.to_string_lossy().into_owned();
+                p.push_str(&iri[start.len()..]);
+                p
+            })
+    }
+
+    fn add_entry(&mut self, base: &Path, e: &BytesStart) -> Result<()> {
+        match e.local_name().as_ref() {
+            b"uri" => {
+                if let (Some(name), Some(uri)) = (attribute(e, "name")?, attribute(e, "uri")?) {
+                    self.uris.insert(name, base.join(file_path(&uri)));
+                }
+            }
+            b"rewriteURI" => {
+                if let (Some(start), Some(prefix)) = (
+                    attribute(e, "uriStartString")?,
+                    attribute(e, "rewritePrefix")?,
+                ) {
+                    self.rewrites.push((start, base.join(file_path(&prefix))));
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+}
+
+fn resolve_base(base: &Path, e: &BytesStart) -> Result<PathBuf> {
+    Ok(match attribute(e, "xml:base")? {
+        Some(b) => base.join(file_path(&b)),
+        None => base.to_path_buf(),
+    })
+}
+
+fn attribute(e: &BytesStart, name: &str) -> Result<Option<String>> {
+    match e.try_get_attribute(name)? {
+        Some(a) => Ok(Some(a.unescape_value()?.into_owned())),
+        None => Ok(None),
+    }
+}
+
+/// Strip the scheme of `file:` URIs so they can be joined as paths.
+fn file_path(uri: &str) -> &str {
+    uri.strip_prefix("file://")
+        .or_else(|| uri.strip_prefix("file:"))
+        .unwrap_or(uri)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_uris_and_rewrites_against_xml_base() {
+        let dir = std::env::temp_dir().join(format!("hyppo-catalog-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("catalog-v001.xml");
+        fs::write(
+            &path,
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
+<catalog prefer="public" xmlns="urn:oasis:names:tc:entity:xmlns:xml:catalog">
+    <uri name="http://example.org/a.owl" uri="imports/a.owl"/>
+    <group xml:base="file:vendor/">
+        <uri name="http://example.org/b.owl" uri="b.owl"/>
+        <rewriteURI uriStartString="http://example.org/" rewritePrefix="example/"/>
+        <rewriteURI uriStartString="http://example.org/obo/" rewritePrefix="obo/"/>
+    </group>
+    <uri name="http://example.org/c.owl" uri="file:/abs/c.owl"/>
+</catalog>
+"#,
+        )
+        .unwrap();
+        let catalog = Catalog::from_file(path.to_str().unwrap()).unwrap();
+        let local = |p: &str| Some(dir.join(p).to_string_lossy().into_owned());
+        assert_eq!(
+            catalog.resolve("http://example.org/a.owl"),
+            local("imports/a.owl")
+        );
+        assert_eq!(
+            catalog.resolve("http://example.org/b.owl"),
+            local("vendor/b.owl")
+        );
+        assert_eq!(
+            catalog.resolve("http://example.org/c.owl"),
+            Some("/abs/c.owl".into())
+        );
+        // The longest matching start wins.
+        assert_eq!(
+            catalog.resolve("http://example.org/obo/bfo.owl"),
+            local("vendor/obo/bfo.owl")
+        );
+        assert_eq!(
+            catalog.resolve("http://example.org/other/x.owl"),
+            local("vendor/example/other/x.owl")
+        );
+        assert_eq!(catalog.resolve("http://other.org/x.owl"), None);
+    }
+}
@@ -1,15 +1,194 @@
-use clap::ArgMatches;
-
+use clap::ValueEnum;
 use horned_owl::io::ParserConfiguration;
 use horned_owl::io::RDFParserConfiguration;
 use serde::{Deserialize, Serialize};
 
+#[derive(Deserialize, Debug, Serialize, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum OntologyFormat {
+    Owx,
+    Ofn,
+    RdfXml,
+    Turtle,
+    NTriples,
+}
+
+/// How class expressions and data ranges are written on entity pages.
+#[derive(Deserialize, Debug, Serialize, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExpressionSyntax {
+    /// Expanded by the `expand_entity` template macro.
+    #[default]
+    Macros,
+    /// Rendered to Manchester OWL syntax, as in Protégé.
+    Manchester,
+}
+
+/// Order of entities in the ontology index and the class tree.
+#[derive(Deserialize, Debug, Serialize, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum EntityOrder {
+    /// Alphabetical by label, case insensitive.
+    #[default]
+    Label,
+    Iri,
+    /// Order of the declarations in the source documents. RDF sources have
+    /// no order and are sorted by IRI.
+    Ontology,
+}
+
+/// Web server for which `build --redirects` writes content negotiation rules.
+#[derive(Deserialize, Debug, Serialize, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum RedirectFormat {
+    /// `.htaccess` for mod_rewrite.
+    Apache,
+    /// `redirects.nginx.conf`, to include in the server block.
+    Nginx,
+    /// `redirects.Caddyfile`, to import in the site block.
+    Caddy,
+    /// `_redirects` and `_headers`.
+    Netlify,
+}
+
+/// Built-in sets of annotation properties for each role.
+#[derive(Deserialize, Debug, Serialize, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum AnnotationPreset {
+    /// `rdfs:label`, `skos:definition`, `skos:example` and `rdfs:comment`.
+    #[default]
+    Default,
+    /// IAO and oboInOwl properties used by OBO Foundry ontologies.
+    Obo,
+    /// SKOS labels, definitions and notes.
+    Skos,
+}
+
+/// What an annotation property is shown as on entity pages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnnotationRole {
+    Label,
+    Definition,
+    Example,
+    Synonym,
+    Comment,
+    EditorNote,
+    Deprecated,
+    ReplacedBy,
+    Consider,
+}
+
+const RDFS: &str = "http://www.w3.org/2000/01/rdf-schema#";
+const SKOS: &str = "http://www.w3.org/2004/02/skos/core#";
+const OBO: &str = "http://purl.obolibrary.org/obo/";
+const OBO_IN_OWL: &str = "http://www.geneontology.org/formats/oboInOwl#";
+const OWL_DEPRECATED: &str = "http://www.w3.org/2002/07/owl#deprecated";
+
+/// The `[annotations]` section: annotation property IRIs for each role, most
+/// preferred first. Roles left out are taken from the preset.
+#[derive(Deserialize, Debug, Serialize, Clone, Default)]
+#[allow(unused)]
+pub struct AnnotationConfig {
+    pub preset: Option<AnnotationPreset>,
+    pub label: Option<Vec<String>>,
+    pub definition: Option<Vec<String>>,
+    pub example: Option<Vec<String>>,
+    pub synonyms: Option<Vec<String>>,
+    pub comment: Option<Vec<String>>,
+    pub editor_note: Option<Vec<String>>,
+    pub deprecated: Option<Vec<String>>,
+    pub replaced_by: Option<Vec<String>>,
+    pub consider: Option<Vec<String>>,
+}
+
+impl AnnotationConfig {
+    /// Annotation property IRIs playing `role`, most preferred first.
+    pub fn properties(&self, role: AnnotationRole) -> Vec<String> {
+        let configured = match role {
+            AnnotationRole::Label => &self.label,
+            AnnotationRole::Definition => &self.definition,
+            AnnotationRole::Example => &self.example,
+            AnnotationRole::Synonym => &self.synonyms,
+            AnnotationRole::Comment => &self.comment,
+            AnnotationRole::EditorNote => &self.editor_note,
+            AnnotationRole::Deprecated => &self.deprecated,
+            AnnotationRole::ReplacedBy => &self.replaced_by,
+            AnnotationRole::Consider => &self.consider,
+        };
+        match configured {
+            Some(iris) => iris.clone(),
+            None => preset_properties(self.preset.unwrap_or_default(), role),
+        }
+    }
+
+    /// Role of the annotation property `iri` and its rank among the
+    /// properties of that role, if it has one.
+    pub fn role(&self, iri: &str) -> Option<(AnnotationRole, usize)> {
+        [
+            AnnotationRole::Label,
+            AnnotationRole::Definition,
+            AnnotationRole::Example,
+            AnnotationRole::Synonym,
+            AnnotationRole::Comment,
+            AnnotationRole::EditorNote,
+            AnnotationRole::Deprecated,
+            AnnotationRole::ReplacedBy,
+            AnnotationRole::Consider,
+        ]
+        .into_iter()
+        .find_map(|role| {
+            let rank = self.properties(role).iter().position(|p| p == iri)?;
+            Some((role, rank))
+        })
+    }
+}
+
+fn preset_properties(preset: AnnotationPreset, role: AnnotationRole) -> Vec<String> {
+    match (preset, role) {
+        (AnnotationPreset::Skos, AnnotationRole::Label) => {
+            vec![format!("{SKOS}prefLabel"), format!("{RDFS}label")]
+        }
+        (_, AnnotationRole::Label) => vec![format!("{RDFS}label")],
+        (AnnotationPreset::Obo, AnnotationRole::Definition) => vec![format!("{OBO}IAO_0000115")],
+        (_, AnnotationRole::Definition) => vec![format!("{SKOS}definition")],
+        (AnnotationPreset::Obo, AnnotationRole::Example) => vec![format!("{OBO}IAO_0000112")],
+        (_, AnnotationRole::Example) => vec![format!("{SKOS}example")],
+        (AnnotationPreset::Obo, AnnotationRole::Synonym) => vec![
+            format!("{OBO_IN_OWL}hasExactSynonym"),
+            format!("{OBO_IN_OWL}hasRelatedSynonym"),
+            format!("{OBO_IN_OWL}hasBroadSynonym"),
+            format!("{OBO_IN_OWL}hasNarrowSynonym"),
+        ],
+        (AnnotationPreset::Skos, AnnotationRole::Synonym) => {
+            vec![format!("{SKOS}altLabel"), format!("{SKOS}hiddenLabel")]
+        }
+        (AnnotationPreset::Default, AnnotationRole::Synonym) => vec![],
+        (AnnotationPreset::Skos, AnnotationRole::Comment) => vec![
+            format!("{RDFS}comment"),
+            format!("{SKOS}note"),
+            format!("{SKOS}scopeNote"),
+        ],
+        (_, AnnotationRole::Comment) => vec![format!("{RDFS}comment")],
+        (AnnotationPreset::Obo, AnnotationRole::EditorNote) => vec![format!("{OBO}IAO_0000116")],
+        (AnnotationPreset::Skos, AnnotationRole::EditorNote) => vec![
+            format!("{SKOS}editorialNote"),
+            format!("{SKOS}changeNote"),
+            format!("{SKOS}historyNote"),
+        ],
+        (AnnotationPreset::Default, AnnotationRole::EditorNote) => vec![],
+        (_, AnnotationRole::Deprecated) => vec![OWL_DEPRECATED.to_string()],
+        (_, AnnotationRole::ReplacedBy) => vec![format!("{OBO}IAO_0100001")],
+        (_, AnnotationRole::Consider) => vec![format!("{OBO_IN_OWL}consider")],
+    }
+}
+
 #[derive(Deserialize, Debug, Serialize, Clone)]
 #[allow(unused)]
 pub struct OntologyConfig {
     pub iri: String,
     pub source: Option<String>,
     pub suffix: Option<String>,
+    pub format: Option<OntologyFormat>,
 }
 
 #[derive(Deserialize, Debug, Serialize, Clone)]
@@ -17,6 +196,12 @@ pub struct OntologyConfig {
 pub struct BuildConfig {
     pub render: bool,
     pub output: String,
+    /// Render the whole ontology into one self-contained `index.html`.
+    #[serde(default)]
+    pub single_page: bool,
+    /// Write content negotiation rules for the entity pages, so not with
+    /// `single_page`.
+    pub redirects: Option<RedirectFormat>,
 }
 #[derive(Deserialize, Debug, Serialize)]
 #[allow(unused)]
@@ -28,12 +213,41 @@ pub struct Settings {
     pub templates: Option<String>,
     pub assets: Option<String>,
     pub build: Option<BuildConfig>,
+    pub strict: Option<bool>,
+    pub catalog: Option<String>,
+    pub cache: Option<String>,
+    pub offline: Option<bool>,
+    pub syntax: Option<ExpressionSyntax>,
+    /// Preferred languages for labels and annotations, most preferred first.
+    /// Defaults to `en`. Untagged values come after the preferred languages,
+    /// and other languages last.
+    pub languages: Option<Vec<String>>,
+    /// Also build one site per preferred language, under `/<lang>/`.
+    pub language_sites: Option<bool>,
+    pub annotations: Option<AnnotationConfig>,
+    /// Leave deprecated entities out of the ontology index. Their pages are
+    /// still built.
+    pub exclude_deprecated: Option<bool>,
+    pub order: Option<EntityOrder>,
+}
+
+impl Settings {
+    /// Path component of the base URL, starting and ending with a slash.
+    pub fn base_path(&self) -> String {
+        let baseurl = self.baseurl.as_deref().unwrap_or("/");
+        let path = match baseurl.split_once("://") {
+            Some((_, rest)) => rest.find('/').map_or("/", |i| &rest[i..]),
+            None => baseurl,
+        };
+        format!("/{}/", path.trim_matches('/')).replace("//", "/")
+    }
 }
 
-pub fn parser_config(matches: &ArgMatches) -> ParserConfiguration {
+pub fn parser_config(settings: &Settings) -> ParserConfiguration {
     ParserConfiguration {
         rdf: RDFParserConfiguration {
-            lax: !matches.get_one::<bool>("strict").unwrap_or(&false),
+            lax: !settings.strict.unwrap_or(false),
+            ..Default::default()
         },
         ..Default::default()
     }
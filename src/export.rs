@@ -0,0 +1,196 @@
+use curie::PrefixMapping;
+use eyre::Result;
+use horned_owl::model::{AnnotatedComponent, AnonymousIndividual, ForIRI};
+use horned_owl::ontology::component_mapped::ComponentMappedOntology;
+use horned_owl::ontology::indexed::ForIndex;
+use horned_owl::visitor::mutable::{VisitMut, WalkMut};
+use oxrdf::{BlankNode, NamedOrBlankNode, Term};
+use oxrdfio::{JsonLdProfileSet, RdfFormat, RdfParser, RdfSerializer};
+use std::collections::HashMap;
+
+/// Machine readable serializations of entities and ontologies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Turtle,
+    RdfXml,
+    JsonLd,
+    Owx,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 4] = [
+        ExportFormat::Turtle,
+        ExportFormat::RdfXml,
+        ExportFormat::JsonLd,
+        ExportFormat::Owx,
+    ];
+
+    pub fn media_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Turtle => "text/turtle",
+            ExportFormat::RdfXml => "application/rdf+xml",
+            ExportFormat::JsonLd => "application/ld+json",
+            ExportFormat::Owx => "application/owl+xml",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportFormat::Turtle => "Turtle",
+            ExportFormat::RdfXml => "RDF/XML",
+            ExportFormat::JsonLd => "JSON-LD",
+            ExportFormat::Owx => "OWL/XML",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Turtle => "ttl",
+            ExportFormat::RdfXml => "rdf",
+            ExportFormat::JsonLd => "jsonld",
+            ExportFormat::Owx => "owx",
+        }
+    }
+
+    pub fn from_media_type(media_type: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|f| f.media_type() == media_type)
+    }
+
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|f| f.extension() == extension)
+    }
+}
+
+/// Prefixes always available in RDF serializations.
+const RDF_PREFIXES: [(&str, &str); 4] = [
+    ("rdf", "http://www.w3.org/1999/02/22-rdf-syntax-ns#"),
+    ("rdfs", "http://www.w3.org/2000/01/rdf-schema#"),
+    ("owl", "http://www.w3.org/2002/07/owl#"),
+    ("xsd", "http://www.w3.org/2001/XMLSchema#"),
+];
+
+/// Serializes `components` as a standalone document. RDF formats are written
+/// to RDF/XML by horned-owl, then converted with the prefixes of `mapping`
+/// and `default` as the empty prefix. OWL/XML is written with full IRIs.
+pub fn write_components<A: ForIRI, AA: ForIndex<A>>(
+    components: impl IntoIterator<Item = AnnotatedComponent<A>>,
+    format: ExportFormat,
+    mapping: &PrefixMapping,
+    default: Option<&str>,
+) -> Result<Vec<u8>> {
+    let ontology: ComponentMappedOntology<A, AA> = components
+        .into_iter()
+        .map(|mut c| {
+            WalkMut::new(NodeIds).annotated_component(&mut c);
+            c
+        })
+        .collect();
+    if format == ExportFormat::Owx {
+        // horned-owl writes shortened IRIs in `IRI` attributes, which readers
+        // take as full IRIs, and entity documents have no `xml:base` to
+        // resolve them against, so no prefixes are given.
+        return horned_owl::io::owx::writer::write(Vec::new(), &ontology, None)
+            .map_err(|e| eyre::eyre!("{e}"));
+    }
+    let rdf_xml = horned_owl::io::rdf::writer::write(Vec::new(), &ontology)
+        .map_err(|e| eyre::eyre!("{e}"))?;
+    let rdf_format = match format {
+        ExportFormat::Turtle => RdfFormat::Turtle,
+        ExportFormat::JsonLd => RdfFormat::JsonLd {
+            profile: JsonLdProfileSet::empty(),
+        },
+        _ => RdfFormat::RdfXml,
+    };
+    let mut prefixes: Vec<(String, String)> = default
+        .map(|d| (String::new(), d.to_string()))
+        .into_iter()
+        .chain(mapping.mappings().map(|(p, i)| (p.clone(), i.clone())))
+        .collect();
+    for (prefix, iri) in RDF_PREFIXES {
+        if !prefixes.iter().any(|(p, i)| p == prefix || i == iri) {
+            prefixes.push((prefix.to_string(), iri.to_string()));
+        }
+    }
+    let mut serializer = RdfSerializer::from_format(rdf_format);
+    for (prefix, iri) in prefixes {
+        serializer = serializer.with_prefix(prefix, iri)?;
+    }
+    let mut writer = serializer.for_writer(Vec::new());
+    // The RDF/XML parser names nested blank nodes randomly, rename them in
+    // order of appearance so that the output is reproducible.
+    let mut blank_nodes: HashMap<BlankNode, BlankNode> = HashMap::new();
+    let mut rename = |b: BlankNode| {
+        let next = blank_nodes.len();
+        blank_nodes
+            .entry(b)
+            .or_insert_with(|| BlankNode::new_unchecked(format!("b{next}")))
+            .clone()
+    };
+    for quad in RdfParser::from_format(RdfFormat::RdfXml).for_slice(&rdf_xml) {
+        let mut quad = quad?;
+        if let NamedOrBlankNode::BlankNode(b) = quad.subject {
+            quad.subject = rename(b).into();
+        }
+        if let Term::BlankNode(b) = quad.object {
+            quad.object = rename(b).into();
+        }
+        writer.serialize_quad(&quad)?;
+    }
+    Ok(writer.finish()?)
+}
+
+/// Strips the `_:` of anonymous individuals read from OFN, which is not
+/// allowed in RDF/XML node IDs.
+struct NodeIds;
+
+impl<A: ForIRI> VisitMut<A> for NodeIds {
+    fn visit_anonymous_individual(&mut self, ai: &mut AnonymousIndividual<A>) {
+        if let Some(id) = ai.0.strip_prefix("_:") {
+            ai.0 = id.to_string().into();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use horned_owl::model::{Build, Class, Component, DeclareClass, RcStr, SubClassOf};
+    use horned_owl::ontology::set::SetOntology;
+    use std::collections::BTreeSet;
+    use std::io::Cursor;
+    use std::rc::Rc;
+
+    #[test]
+    fn owx_reads_back_with_the_same_iris() {
+        let b: Build<RcStr> = Build::new();
+        let components: BTreeSet<AnnotatedComponent<RcStr>> = [
+            Component::DeclareClass(DeclareClass(b.class("http://example.org/t#A"))),
+            Component::SubClassOf(SubClassOf {
+                sub: b.class("http://example.org/t#A").into(),
+                sup: Class(b.iri("http://purl.obolibrary.org/obo/BFO_0000002")).into(),
+            }),
+        ]
+        .into_iter()
+        .map(AnnotatedComponent::from)
+        .collect();
+        let mut mapping = PrefixMapping::default();
+        mapping
+            .add_prefix("obo", "http://purl.obolibrary.org/obo/")
+            .unwrap();
+        mapping.set_default("http://example.org/t#");
+        let owx = write_components::<RcStr, Rc<AnnotatedComponent<RcStr>>>(
+            components.clone(),
+            ExportFormat::Owx,
+            &mapping,
+            Some("http://example.org/t#"),
+        )
+        .unwrap();
+        let (read, _): (SetOntology<RcStr>, _) =
+            horned_owl::io::owx::reader::read_with_build(Cursor::new(&owx), &b).unwrap();
+        let read: BTreeSet<AnnotatedComponent<RcStr>> = read
+            .into_iter()
+            .filter(|c| !matches!(c.component, Component::OntologyID(_)))
+            .collect();
+        assert_eq!(read, components);
+    }
+}
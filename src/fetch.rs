@@ -0,0 +1,193 @@
+use eyre::Result;
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+
+use crate::config::Settings;
+
+/// Media types requested when downloading an ontology, in order of
+/// preference.
+const ACCEPT: &str = "application/rdf+xml, text/turtle;q=0.9, application/owl+xml;q=0.8, \
+                      text/owl-functional;q=0.7, application/n-triples;q=0.6, */*;q=0.1";
+
+const DEFAULT_CACHE_DIR: &str = ".hyppo-cache";
+
+/// Downloads remote ontology sources into a local cache.
+///
+/// Every URL has an index file in the cache recording the ETag of the last
+/// download, and the body is stored under a name keyed by URL and ETag.
+/// Cached copies are revalidated with `If-None-Match` and used as they are
+/// when offline or when the server cannot be reached.
+#[derive(Debug)]
+pub struct Fetcher {
+    cache_dir: PathBuf,
+    offline: bool,
+}
+
+impl Fetcher {
+    pub fn new(settings: &Settings) -> Self {
+        Fetcher {
+            cache_dir: PathBuf::from(settings.cache.as_deref().unwrap_or(DEFAULT_CACHE_DIR)),
+            offline: settings.offline.unwrap_or(false),
+        }
+    }
+
+    pub fn is_remote(source: &str) -> bool {
+        source.starts_with("http://") || source.starts_with("https://")
+    }
+
+    /// Local path holding the content of `url`, downloading it if needed.
+    pub fn fetch(&self, url: &str) -> Result<String> {
+        let index = self.cache_dir.join(format!("{}.index", cache_key(url)));
+        let cached: Option<(String, PathBuf)> = fs::read_to_string(&index).ok().and_then(|s| {
+            let (etag, file) = s.split_once('\n')?;
+            let path = self.cache_dir.join(file.trim());
+            path.exists().then(|| (etag.to_string(), path))
+        });
+        if self.offline {
+            return match cached {
+                Some((_, path)) => Ok(path.to_string_lossy().into_owned()),
+                None => Err(eyre::eyre!(
+                    "{url} is not cached and hyppo is running offline"
+                )),
+            };
+        }
+        let mut request = ureq::get(url).set("Accept", ACCEPT);
+        if let Some((etag, _)) = cached.as_ref().filter(|(etag, _)| !etag.is_empty()) {
+            request = request.set("If-None-Match", etag);
+        }
+        let response = match request.call() {
+            Ok(r) => r,
+            Err(e) => {
+                return match cached {
+                    Some((_, path)) => {
+                        println!("Warning: could not fetch {url} ({e}), using cached copy");
+                        Ok(path.to_string_lossy().into_owned())
+                    }
+                    None => Err(eyre::eyre!("Could not fetch {url}: {e}")),
+                };
+            }
+        };
+        if response.status() == 304
+            && let Some((_, path)) = cached
+        {
+            println!("Using cached {url}");
+            return Ok(path.to_string_lossy().into_owned());
+        }
+        let etag = response.header("ETag").unwrap_or_default().to_string();
+        let file = format!(
+            "{}.{}",
+            cache_key(&format!("{url} {etag}")),
+            extension(response.content_type())
+        );
+        let mut body = vec![];
+        response
+            .into_reader()
+            .read_to_end(&mut body)
+            .map_err(|e| eyre::eyre!("Could not download {url}: {e}"))?;
+        fs::create_dir_all(&self.cache_dir)?;
+        if let Some((_, old)) = cached {
+            fs::remove_file(old)?;
+        }
+        let path = self.cache_dir.join(&file);
+        fs::write(&path, body)?;
+        fs::write(&index, format!("{etag}\n{file}"))?;
+        println!("Downloaded {url}");
+        Ok(path.to_string_lossy().into_owned())
+    }
+}
+
+/// File extension for a downloaded document, so the loader can fall back
+/// on it when the content cannot be sniffed.
+fn extension(content_type: &str) -> &'static str {
+    match content_type {
+        "application/rdf+xml" => "rdf",
+        "text/turtle" | "application/x-turtle" => "ttl",
+        "application/owl+xml" => "owx",
+        "text/owl-functional" => "ofn",
+        "application/n-triples" => "nt",
+        _ => "owl",
+    }
+}
+
+/// Stable file name for a cache entry (64 bit FNV-1a).
+fn cache_key(s: &str) -> String {
+    let hash = s.bytes().fold(0xcbf29ce484222325u64, |h, b| {
+        (h ^ b as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{hash:016x}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use tiny_http::{Header, Response, Server};
+
+    fn fetcher(name: &str, offline: bool) -> Fetcher {
+        let cache_dir =
+            std::env::temp_dir().join(format!("hyppo-fetch-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&cache_dir);
+        Fetcher { cache_dir, offline }
+    }
+
+    /// Serves one ontology with ETag `"v1"`, answering 304 to requests that
+    /// already have it. Returns its URL and the `If-None-Match` header of
+    /// each of the `requests` it handles.
+    fn serve(requests: usize) -> (String, thread::JoinHandle<Vec<Option<String>>>) {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/onto.ttl", server.server_addr());
+        let handle = thread::spawn(move || {
+            let mut seen = vec![];
+            for request in server.incoming_requests().take(requests) {
+                let etag = request
+                    .headers()
+                    .iter()
+                    .find(|h| h.field.equiv("If-None-Match"))
+                    .map(|h| h.value.to_string());
+                let response = if etag.as_deref() == Some("\"v1\"") {
+                    Response::from_data(vec![]).with_status_code(304)
+                } else {
+                    Response::from_string("first")
+                        .with_header(Header::from_bytes("ETag", "\"v1\"").unwrap())
+                        .with_header(Header::from_bytes("Content-Type", "text/turtle").unwrap())
+                };
+                request.respond(response).unwrap();
+                seen.push(etag);
+            }
+            seen
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn not_modified_keeps_the_cached_body() {
+        let (url, server) = serve(2);
+        let fetcher = fetcher("etag", false);
+        let first = fetcher.fetch(&url).unwrap();
+        let second = fetcher.fetch(&url).unwrap();
+        assert_eq!(first, second);
+        assert!(first.ends_with(".ttl"));
+        assert_eq!(fs::read_to_string(&second).unwrap(), "first");
+        assert_eq!(server.join().unwrap(), [None, Some("\"v1\"".to_string())]);
+    }
+
+    #[test]
+    fn offline_uses_only_the_cache() {
+        let (url, server) = serve(1);
+        let online = fetcher("offline", false);
+        let offline = Fetcher {
+            offline: true,
+            ..fetcher("offline-miss", false)
+        };
+        let e = offline.fetch(&url).unwrap_err();
+        assert!(e.to_string().contains("is not cached"), "{e}");
+        let path = online.fetch(&url).unwrap();
+        server.join().unwrap();
+        let offline = Fetcher {
+            offline: true,
+            ..online
+        };
+        assert_eq!(offline.fetch(&url).unwrap(), path);
+    }
+}
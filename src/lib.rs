@@ -1,2 +1,11 @@
+pub mod catalog;
 pub mod config;
+pub mod export;
+pub mod fetch;
+pub mod loader;
+pub mod manchester;
+pub mod redirects;
 pub mod render_html;
+pub mod search;
+pub mod serve;
+pub mod usage;
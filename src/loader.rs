@@ -0,0 +1,589 @@
+use curie::PrefixMapping;
+use eyre::Result;
+use horned_owl::error::{HornedError, Location};
+use horned_owl::io::ParserConfiguration;
+use horned_owl::model::{
+    AnnotatedComponent, Build, Component, ForIRI, IRI, Import, MutableOntology, Ontology,
+    OntologyID,
+};
+use horned_owl::ontology::indexed::ForIndex;
+use horned_owl::ontology::set::SetOntology;
+use oxrdfio::{RdfFormat, RdfParser};
+use oxrdfxml::RdfXmlParser;
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::fmt;
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+
+use crate::catalog::Catalog;
+use crate::config::{OntologyConfig, OntologyFormat, Settings, parser_config};
+use crate::fetch::Fetcher;
+
+/// Number of bytes inspected when sniffing the format of a source.
+const SNIFF_LEN: usize = 4096;
+
+impl OntologyFormat {
+    pub fn from_extension(path: &str) -> Option<Self> {
+        let ext = Path::new(path).extension()?.to_str()?.to_lowercase();
+        match ext.as_str() {
+            "owx" => Some(OntologyFormat::Owx),
+            "ofn" | "fss" => Some(OntologyFormat::Ofn),
+            "owl" | "rdf" | "xml" => Some(OntologyFormat::RdfXml),
+            "ttl" => Some(OntologyFormat::Turtle),
+            "nt" => Some(OntologyFormat::NTriples),
+            _ => None,
+        }
+    }
+
+    /// Guess the format from the first bytes of a document.
+    ///
+    /// XML documents are told apart by their root element, `Ontology` for
+    /// OWL/XML and `rdf:RDF` for RDF/XML. Turtle is recognised by its
+    /// directives and OFN by a leading `Prefix(` or `Ontology(`.
+    pub fn sniff(content: &[u8]) -> Option<Self> {
+        let head = String::from_utf8_lossy(&content[..content.len().min(SNIFF_LEN)]);
+        let mut rest = head.trim_start_matches('\u{feff}').trim_start();
+        loop {
+            if let Some(r) = rest.strip_prefix("<?") {
+                rest = r.split_once("?>")?.1.trim_start();
+            } else if let Some(r) = rest.strip_prefix("<!--") {
+                rest = r.split_once("-->")?.1.trim_start();
+            } else if let Some(r) = rest.strip_prefix("<!") {
+                rest = skip_doctype(r)?.trim_start();
+            } else if rest.starts_with('#') {
+                rest = rest.split_once('\n')?.1.trim_start();
+            } else {
+                break;
+            }
+        }
+        if let Some(r) = rest.strip_prefix('<') {
+            let name: String = r
+                .chars()
+                .take_while(|c| !c.is_whitespace() && *c != '>' && *c != '/')
+                .collect();
+            return match name.rsplit(':').next() {
+                Some("Ontology") => Some(OntologyFormat::Owx),
+                Some("RDF") => Some(OntologyFormat::RdfXml),
+                // N-Triples lines start with an IRI, which Turtle also accepts.
+                _ if is_iri(r.split_once('>')?.0) => Some(OntologyFormat::Turtle),
+                _ => None,
+            };
+        }
+        let lower = rest.to_lowercase();
+        if lower.starts_with("@prefix") || lower.starts_with("@base") {
+            Some(OntologyFormat::Turtle)
+        } else if rest.starts_with("Prefix(") || rest.starts_with("Ontology(") {
+            Some(OntologyFormat::Ofn)
+        } else if lower.starts_with("prefix") || lower.starts_with("base") {
+            Some(OntologyFormat::Turtle)
+        } else {
+            None
+        }
+    }
+
+    fn rdf_format(&self) -> Option<RdfFormat> {
+        match self {
+            OntologyFormat::RdfXml => Some(RdfFormat::RdfXml),
+            OntologyFormat::Turtle => Some(RdfFormat::Turtle),
+            OntologyFormat::NTriples => Some(RdfFormat::NTriples),
+            OntologyFormat::Owx | OntologyFormat::Ofn => None,
+        }
+    }
+}
+
+impl fmt::Display for OntologyFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            OntologyFormat::Owx => "OWL/XML",
+            OntologyFormat::Ofn => "OWL Functional Syntax",
+            OntologyFormat::RdfXml => "RDF/XML",
+            OntologyFormat::Turtle => "Turtle",
+            OntologyFormat::NTriples => "N-Triples",
+        };
+        write!(f, "{name}")
+    }
+}
+
+fn is_iri(s: &str) -> bool {
+    s.contains(':') && !s.chars().any(char::is_whitespace)
+}
+
+/// Skip a `<!DOCTYPE ...>` declaration, including an internal subset.
+fn skip_doctype(s: &str) -> Option<&str> {
+    let mut depth = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            '>' if depth == 0 => return Some(&s[i + 1..]),
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Where the format used to parse a source came from.
+#[derive(Debug, Clone, Copy)]
+enum FormatOrigin {
+    Configured,
+    Detected,
+    Extension,
+}
+
+impl fmt::Display for FormatOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FormatOrigin::Configured => write!(f, "configured"),
+            FormatOrigin::Detected => write!(f, "detected from content"),
+            FormatOrigin::Extension => write!(f, "inferred from file extension"),
+        }
+    }
+}
+
+/// Ontology recording the entities declared by the parser in the order it
+/// reads them, which is the order of the source document.
+struct DocumentOrder<A: ForIRI> {
+    ontology: SetOntology<A>,
+    declared: Vec<IRI<A>>,
+}
+
+impl<A: ForIRI> Default for DocumentOrder<A> {
+    fn default() -> Self {
+        DocumentOrder {
+            ontology: SetOntology::new(),
+            declared: vec![],
+        }
+    }
+}
+
+impl<A: ForIRI> Ontology<A> for DocumentOrder<A> {}
+
+impl<A: ForIRI> MutableOntology<A> for DocumentOrder<A> {
+    fn insert<AA>(&mut self, cmp: AA) -> bool
+    where
+        AA: Into<AnnotatedComponent<A>>,
+    {
+        let cmp = cmp.into();
+        let declared = match &cmp.component {
+            Component::DeclareClass(d) => Some(&d.0.0),
+            Component::DeclareObjectProperty(d) => Some(&d.0.0),
+            Component::DeclareAnnotationProperty(d) => Some(&d.0.0),
+            Component::DeclareDataProperty(d) => Some(&d.0.0),
+            Component::DeclareNamedIndividual(d) => Some(&d.0.0),
+            Component::DeclareDatatype(d) => Some(&d.0.0),
+            _ => None,
+        };
+        self.declared.extend(declared.cloned());
+        self.ontology.insert(cmp)
+    }
+
+    fn take(&mut self, cmp: &AnnotatedComponent<A>) -> Option<AnnotatedComponent<A>> {
+        self.ontology.take(cmp)
+    }
+}
+
+/// An ontology with the entities it declares, in document order.
+pub type OrderedOntology<A> = (SetOntology<A>, Vec<IRI<A>>);
+
+/// Reads an ontology with its prefixes and the entities it declares, in
+/// document order. RDF documents have no order, so nothing is recorded for
+/// them.
+pub fn read_ontology<A: ForIRI, AA: ForIndex<A>>(
+    source: &str,
+    format: Option<OntologyFormat>,
+    config: ParserConfiguration,
+    build: &Build<A>,
+) -> Result<(SetOntology<A>, PrefixMapping, Vec<IRI<A>>)> {
+    let content = fs::read(source).map_err(|e| eyre::eyre!("Could not read {source}: {e}"))?;
+    let (format, origin) = if let Some(f) = format {
+        (f, FormatOrigin::Configured)
+    } else if let Some(f) = OntologyFormat::sniff(&content) {
+        (f, FormatOrigin::Detected)
+    } else if let Some(f) = OntologyFormat::from_extension(source) {
+        (f, FormatOrigin::Extension)
+    } else {
+        return Err(eyre::eyre!(
+            "Could not detect the format of {source}, set `format` in the ontology config"
+        ));
+    };
+    println!("Reading {source} as {format} ({origin})");
+    let parse_error = |line: Option<usize>, e: &dyn fmt::Display| {
+        let position = match line {
+            Some(l) => format!("{source}:{l}"),
+            None => source.to_string(),
+        };
+        eyre::eyre!("{position}: could not parse as {format} ({origin}): {e}")
+    };
+    let ordered =
+        |(o, mapping): (DocumentOrder<A>, PrefixMapping)| (o.ontology, mapping, o.declared);
+    match format {
+        OntologyFormat::Owx => {
+            horned_owl::io::owx::reader::read_with_build(Cursor::new(&content), build)
+                .map(ordered)
+                .map_err(|e| parse_error(horned_error_line(&e, &content), &e))
+        }
+        OntologyFormat::Ofn => {
+            horned_owl::io::ofn::reader::read_with_build(Cursor::new(&content), build)
+                .map(ordered)
+                .map_err(|e| parse_error(horned_error_line(&e, &content), &e))
+        }
+        OntologyFormat::RdfXml | OntologyFormat::Turtle | OntologyFormat::NTriples => {
+            let rdf_format = format.rdf_format();
+            // horned-owl panics on RDF syntax errors, so check the syntax first.
+            let mapping = match rdf_format {
+                Some(rf) => {
+                    check_rdf_syntax(&content, rf).map_err(|(line, e)| parse_error(line, &e))?
+                }
+                None => PrefixMapping::default(),
+            };
+            let mut rdf_config = config;
+            rdf_config.rdf.format = rdf_format;
+            let (o, incomplete) = horned_owl::io::rdf::reader::read_with_build::<A, AA, _>(
+                &mut Cursor::new(&content),
+                build,
+                rdf_config,
+            )
+            .map_err(|e| parse_error(horned_error_line(&e, &content), &e))?;
+            if !incomplete.is_complete() {
+                if config.rdf.lax {
+                    println!("Warning: {source} was only partially parsed into OWL");
+                } else {
+                    return Err(eyre::eyre!(
+                        "{source} could not be fully parsed into OWL: {incomplete:?}"
+                    ));
+                }
+            }
+            Ok((o.into(), mapping, vec![]))
+        }
+    }
+}
+
+/// Finds local files for ontology sources and imported ontology IRIs.
+///
+/// Sources are looked up in the configuration, then in the XML catalog, and
+/// remote sources are downloaded into the cache.
+pub struct Resolver<'a> {
+    settings: &'a Settings,
+    catalog: Option<Catalog>,
+    fetcher: Fetcher,
+}
+
+impl<'a> Resolver<'a> {
+    pub fn new(settings: &'a Settings) -> Result<Self> {
+        let catalog = match &settings.catalog {
+            Some(c) => Some(Catalog::from_file(c)?),
+            None => None,
+        };
+        Ok(Resolver {
+            settings,
+            catalog,
+            fetcher: Fetcher::new(settings),
+        })
+    }
+
+    /// Local source of an ontology, either configured directly or found in
+    /// the catalog.
+    pub fn source(&self, config: &OntologyConfig) -> Result<Option<String>> {
+        match &config.source {
+            Some(s) if Fetcher::is_remote(s) => self.fetcher.fetch(s).map(Some),
+            Some(s) => Ok(Some(s.clone())),
+            None => Ok(self.catalog.as_ref().and_then(|c| c.resolve(&config.iri))),
+        }
+    }
+
+    /// Local source for an imported ontology IRI.
+    fn import(&self, iri: &str) -> Result<Option<(String, Option<OntologyFormat>)>> {
+        if let Some(path) = self.catalog.as_ref().and_then(|c| c.resolve(iri)) {
+            return Ok(Some((path, None)));
+        }
+        if let Some(imp) = self
+            .settings
+            .import
+            .iter()
+            .flatten()
+            .find(|imp| imp.iri == iri && imp.source.is_some())
+        {
+            return Ok(self.source(imp)?.map(|s| (s, imp.format)));
+        }
+        if let Some(path) = iri.strip_prefix("file://") {
+            return Ok(Path::new(path).exists().then(|| (path.to_string(), None)));
+        }
+        if Fetcher::is_remote(iri) {
+            return self.fetcher.fetch(iri).map(|p| Some((p, None)));
+        }
+        Ok(None)
+    }
+}
+
+/// An ontology waiting to be loaded while walking the imports closure.
+struct PendingImport {
+    iri: Option<String>,
+    source: Option<String>,
+    format: Option<OntologyFormat>,
+}
+
+/// Load every ontology in the imports closure of `ontology`.
+///
+/// The sources of the configured imports are loaded first, then the
+/// `owl:imports` of each loaded ontology are followed transitively. Imports
+/// that cannot be resolved to a source are reported and skipped, unless a
+/// catalog is configured, in which case they are an error.
+pub fn read_imports_closure<A: ForIRI, AA: ForIndex<A>>(
+    ontology: &SetOntology<A>,
+    resolver: &Resolver,
+    build: &Build<A>,
+) -> Result<Vec<OrderedOntology<A>>> {
+    let settings = resolver.settings;
+    let config = parser_config(settings);
+    let mut seen: HashSet<String> = ontology_iri(ontology).into_iter().collect();
+    seen.extend(settings.ontology.source.clone());
+    let mut pending: VecDeque<PendingImport> = VecDeque::new();
+    for imp in settings.import.iter().flatten() {
+        if let Some(source) = resolver.source(imp)? {
+            pending.push_back(PendingImport {
+                iri: None,
+                source: Some(source),
+                format: imp.format,
+            });
+        }
+    }
+    pending.extend(import_iris(ontology).into_iter().map(|iri| PendingImport {
+        iri: Some(iri),
+        source: None,
+        format: None,
+    }));
+    let mut imports = vec![];
+    let mut unresolved = vec![];
+    while let Some(p) = pending.pop_front() {
+        let (source, format) = match (p.source, &p.iri) {
+            (Some(s), _) => (s, p.format),
+            (None, Some(i)) if seen.contains(i) => continue,
+            (None, Some(i)) => match resolver.import(i) {
+                Ok(Some(r)) => r,
+                Ok(None) => {
+                    unresolved.push(i.clone());
+                    continue;
+                }
+                Err(e) => {
+                    unresolved.push(format!("{i} ({e})"));
+                    continue;
+                }
+            },
+            (None, None) => continue,
+        };
+        if !seen.insert(source.clone()) {
+            continue;
+        }
+        let (o, _, declared) = read_ontology::<A, AA>(&source, format, config, build)?;
+        seen.extend(p.iri);
+        seen.extend(ontology_iri(&o));
+        pending.extend(import_iris(&o).into_iter().map(|iri| PendingImport {
+            iri: Some(iri),
+            source: None,
+            format: None,
+        }));
+        imports.push((o, declared));
+    }
+    unresolved.retain(|i| !seen.contains(i));
+    if !unresolved.is_empty() {
+        if let Some(c) = &resolver.catalog {
+            return Err(eyre::eyre!(
+                "Could not resolve the following imports with catalog {}:\n  {}",
+                c.path(),
+                unresolved.join("\n  ")
+            ));
+        }
+        for i in unresolved {
+            println!("Warning: could not resolve import {i}, skipping");
+        }
+    }
+    Ok(imports)
+}
+
+fn ontology_iri<A: ForIRI>(ontology: &SetOntology<A>) -> Option<String> {
+    ontology.iter().find_map(|ac| match &ac.component {
+        Component::OntologyID(OntologyID { iri: Some(i), .. }) => Some(i.to_string()),
+        _ => None,
+    })
+}
+
+/// Imports of an ontology, sorted so that the closure is loaded in the same
+/// order on every run.
+fn import_iris<A: ForIRI>(ontology: &SetOntology<A>) -> Vec<String> {
+    let mut iris: Vec<String> = ontology
+        .iter()
+        .filter_map(|ac| match &ac.component {
+            Component::Import(Import(i)) => Some(i.to_string()),
+            _ => None,
+        })
+        .collect();
+    iris.sort();
+    iris
+}
+
+/// Parse the triples of a document, returning the prefixes it declares, or
+/// the first syntax error and the line (1-based) it was found at.
+fn check_rdf_syntax(
+    content: &[u8],
+    format: RdfFormat,
+) -> Result<PrefixMapping, (Option<usize>, String)> {
+    let mut prefixes: BTreeMap<String, String> = BTreeMap::new();
+    if format == RdfFormat::RdfXml {
+        let mut parser = RdfXmlParser::new().for_slice(content);
+        while let Some(triple) = parser.next() {
+            if let Err(e) = triple {
+                let line = line_at(content, parser.buffer_position());
+                return Err((Some(line), e.to_string()));
+            }
+            // Namespaces are scoped to their element, so collect them as
+            // they are read.
+            add_prefixes(&mut prefixes, parser.prefixes());
+        }
+    } else {
+        let mut parser = RdfParser::from_format(format).for_slice(content);
+        for quad in parser.by_ref() {
+            if let Err(e) = quad {
+                let line = e.location().map(|l| l.start.line as usize + 1);
+                return Err((line, e.to_string()));
+            }
+        }
+        add_prefixes(&mut prefixes, parser.prefixes());
+    }
+    let mut mapping = PrefixMapping::default();
+    for (prefix, iri) in prefixes {
+        if let Err(e) = mapping.add_prefix(&prefix, &iri) {
+            println!("Warning: ignoring prefix {prefix}: {e:?}");
+        }
+    }
+    Ok(mapping)
+}
+
+/// Adds the prefixes not declared yet, the first declaration of a prefix
+/// winning. The empty prefix is left to the ontology IRI.
+fn add_prefixes<'a>(
+    prefixes: &mut BTreeMap<String, String>,
+    declared: impl Iterator<Item = (&'a str, &'a str)>,
+) {
+    for (prefix, iri) in declared {
+        if !prefix.is_empty() && !prefixes.contains_key(prefix) {
+            prefixes.insert(prefix.to_string(), iri.to_string());
+        }
+    }
+}
+
+/// Line number (1-based) of the location reported by a horned-owl error.
+fn horned_error_line(e: &HornedError, content: &[u8]) -> Option<usize> {
+    let offset = match e {
+        HornedError::ParserError(_, l) | HornedError::ValidityError(_, l) => match l {
+            Location::BytePosition(p) => *p,
+            Location::ByteSpan(r) => r.start,
+            Location::Unknown => return None,
+        },
+        _ => return None,
+    };
+    Some(line_at(content, offset))
+}
+
+fn line_at(content: &[u8], offset: u64) -> usize {
+    let offset = (offset as usize).min(content.len());
+    content[..offset].iter().filter(|b| **b == b'\n').count() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use horned_owl::model::{AnnotatedComponent, RcStr};
+    use std::rc::Rc;
+
+    const OFN: &str = "Prefix(:=<http://example.org/t#>)
+Ontology(<http://example.org/t>
+Declaration(Class(:A))
+)
+";
+
+    const TURTLE: &str = "@prefix owl: <http://www.w3.org/2002/07/owl#> .
+<http://example.org/t> a owl:Ontology .
+";
+
+    const RDF_XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE rdf:RDF [ <!ENTITY owl "http://www.w3.org/2002/07/owl#"> ]>
+<!-- An RDF/XML document -->
+<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+</rdf:RDF>
+"#;
+
+    /// Writes `content` to a file named `name` in a temporary directory.
+    fn source(name: &str, content: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("hyppo-loader-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, content).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    fn read(source: &str) -> Result<(SetOntology<RcStr>, PrefixMapping, Vec<IRI<RcStr>>)> {
+        read_ontology::<RcStr, Rc<AnnotatedComponent<RcStr>>>(
+            source,
+            None,
+            ParserConfiguration::default(),
+            &Build::new(),
+        )
+    }
+
+    #[test]
+    fn content_wins_over_a_misleading_extension() {
+        let path = source("functional.owl", OFN);
+        let (_, _, declared) = read(&path).unwrap();
+        assert_eq!(
+            declared.iter().map(|i| i.to_string()).collect::<Vec<_>>(),
+            ["http://example.org/t#A"]
+        );
+        let path = source("turtle.rdf", TURTLE);
+        assert!(read(&path).is_ok());
+    }
+
+    #[test]
+    fn sniffs_turtle_and_rdf_xml() {
+        assert_eq!(
+            OntologyFormat::sniff(TURTLE.as_bytes()),
+            Some(OntologyFormat::Turtle)
+        );
+        assert_eq!(
+            OntologyFormat::sniff(b"PREFIX owl: <http://www.w3.org/2002/07/owl#>"),
+            Some(OntologyFormat::Turtle)
+        );
+        assert_eq!(
+            OntologyFormat::sniff(b"<http://example.org/a> <http://example.org/b> \"c\" ."),
+            Some(OntologyFormat::Turtle)
+        );
+        assert_eq!(
+            OntologyFormat::sniff(RDF_XML.as_bytes()),
+            Some(OntologyFormat::RdfXml)
+        );
+        assert_eq!(
+            OntologyFormat::sniff(
+                b"<?xml version=\"1.0\"?>\n<Ontology xmlns=\"http://www.w3.org/2002/07/owl#\"/>"
+            ),
+            Some(OntologyFormat::Owx)
+        );
+        assert_eq!(
+            OntologyFormat::sniff(OFN.as_bytes()),
+            Some(OntologyFormat::Ofn)
+        );
+    }
+
+    #[test]
+    fn falls_back_to_the_extension() {
+        let path = source("empty.ttl", "");
+        assert!(read(&path).is_ok());
+    }
+
+    #[test]
+    fn unknown_format_is_an_error() {
+        assert_eq!(OntologyFormat::sniff(b"hello world"), None);
+        let path = source("unknown.dat", "hello world");
+        let e = read(&path).unwrap_err();
+        assert!(e.to_string().contains("Could not detect the format"), "{e}");
+    }
+}
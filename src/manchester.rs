@@ -0,0 +1,385 @@
+use crate::render_html::{
+    CardDisplay, DataRangeDisplay, DisplayComp, EntityDisplay, FacetDisplay, LiteralDisplay,
+    RelDisplay,
+};
+
+const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+
+/// Renders class expressions and data ranges in Manchester OWL syntax, as
+/// plain text or as HTML linking every named entity to its page.
+pub trait Manchester {
+    fn write_manchester(&self, w: &mut Writer);
+
+    /// Whether the expression can be nested without parentheses.
+    fn is_atomic(&self) -> bool;
+
+    fn manchester_text(&self) -> String {
+        let mut w = Writer::default();
+        self.write_manchester(&mut w);
+        w.out
+    }
+
+    fn manchester_html(&self) -> String {
+        let mut w = Writer {
+            html: true,
+            ..Default::default()
+        };
+        self.write_manchester(&mut w);
+        w.out
+    }
+}
+
+/// Output buffer of the Manchester renderer, as plain text or as HTML.
+#[derive(Default)]
+pub struct Writer {
+    html: bool,
+    out: String,
+}
+
+impl Writer {
+    fn entity(&mut self, e: &EntityDisplay) {
+        if self.html {
+            self.out.push_str(&format!(
+                "<a href=\"{}\" class=\"crossref\">{}</a>",
+                escape(&e.url),
+                escape(&e.display)
+            ));
+        } else {
+            self.out.push_str(&quote_name(&e.display));
+        }
+    }
+
+    fn keyword(&mut self, k: &str) {
+        if self.html {
+            self.out
+                .push_str(&format!("<span class=\"manchester-keyword\">{k}</span>"));
+        } else {
+            self.out.push_str(k);
+        }
+    }
+
+    /// Keyword between two operands.
+    fn infix(&mut self, k: &str) {
+        self.out.push(' ');
+        self.keyword(k);
+        self.out.push(' ');
+    }
+
+    fn text(&mut self, s: &str) {
+        if self.html {
+            self.out.push_str(&escape(s));
+        } else {
+            self.out.push_str(s);
+        }
+    }
+
+    fn quoted(&mut self, l: &str) {
+        let quoted = format!("\"{}\"", l.replace('\\', "\\\\").replace('"', "\\\""));
+        self.text(&quoted);
+    }
+
+    /// Literal with its language tag or datatype.
+    fn typed_literal(&mut self, l: &LiteralDisplay) {
+        match (&l.lang, &l.datatype) {
+            (Some(lang), _) => {
+                self.quoted(&l.value);
+                self.text(&format!("@{lang}"));
+            }
+            (None, Some(dt)) => self.typed(&l.value, dt),
+            (None, None) => self.quoted(&l.value),
+        }
+    }
+
+    /// Literal of datatype `dt`, leaving out `xsd:string` and written bare
+    /// in the canonical form Manchester syntax has for integers, decimals,
+    /// floats and booleans, e.g. `1`, `1.0`, `1.0f` and `true`.
+    fn typed(&mut self, value: &str, dt: &EntityDisplay) {
+        let name = dt.iri.strip_prefix(XSD);
+        if name == Some("string") {
+            return self.quoted(value);
+        }
+        match name.and_then(|n| bare_literal(value, n)) {
+            Some(bare) => self.text(&bare),
+            None => {
+                self.quoted(value);
+                self.out.push_str("^^");
+                self.entity(dt);
+            }
+        }
+    }
+
+    /// Facet of a restriction of `dt`. Values have the restricted datatype,
+    /// except lengths and digit counts, which are integers, and patterns and
+    /// language ranges, which are strings.
+    fn facet(&mut self, f: &FacetDisplay, dt: &EntityDisplay) {
+        self.text(&f.facet);
+        self.out.push(' ');
+        match f.facet.as_str() {
+            "length" | "minLength" | "maxLength" | "totalDigits" | "fractionDigits" => {
+                match bare_literal(&f.value, "integer") {
+                    Some(n) => self.text(&n),
+                    None => self.quoted(&f.value),
+                }
+            }
+            "pattern" | "langRange" => self.quoted(&f.value),
+            _ => self.typed(&f.value, dt),
+        }
+    }
+
+    fn nested<M: Manchester + ?Sized>(&mut self, m: &M) {
+        if m.is_atomic() {
+            m.write_manchester(self);
+        } else {
+            self.out.push('(');
+            m.write_manchester(self);
+            self.out.push(')');
+        }
+    }
+
+    fn join<M: Manchester>(&mut self, items: &[M], keyword: &str) {
+        for (i, m) in items.iter().enumerate() {
+            if i > 0 {
+                self.infix(keyword);
+            }
+            self.nested(m);
+        }
+    }
+
+    fn one_of<T>(&mut self, items: &[T], mut item: impl FnMut(&mut Self, &T)) {
+        self.out.push('{');
+        for (i, t) in items.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+            item(self, t);
+        }
+        self.out.push('}');
+    }
+
+    fn restriction(&mut self, r: &RelDisplay, keyword: &str) {
+        self.nested(r.rel.as_ref());
+        self.infix(keyword);
+        self.nested(r.ce.as_ref());
+    }
+
+    fn cardinality(&mut self, c: &CardDisplay, keyword: &str) {
+        self.nested(c.rel.as_ref());
+        self.infix(keyword);
+        self.out.push_str(&c.n.to_string());
+        self.out.push(' ');
+        self.nested(c.ce.as_ref());
+    }
+}
+
+impl Manchester for DisplayComp {
+    fn write_manchester(&self, w: &mut Writer) {
+        match self {
+            DisplayComp::Simple(e) => w.entity(e),
+            DisplayComp::Anonymous(a) => w.text(a),
+            DisplayComp::And(g) => w.join(&g.0, "and"),
+            DisplayComp::Or(g) => w.join(&g.0, "or"),
+            DisplayComp::OneOf(g) => w.one_of(&g.0, |w, i| i.write_manchester(w)),
+            DisplayComp::Some(r) | DisplayComp::DataSome(r) => w.restriction(r, "some"),
+            DisplayComp::All(r) | DisplayComp::DataAll(r) => w.restriction(r, "only"),
+            DisplayComp::Value(r) => w.restriction(r, "value"),
+            DisplayComp::Self_(op) => {
+                w.nested(op.as_ref());
+                w.out.push(' ');
+                w.keyword("Self");
+            }
+            DisplayComp::Min(c) => w.cardinality(c, "min"),
+            DisplayComp::Max(c) => w.cardinality(c, "max"),
+            DisplayComp::Exactly(c) => w.cardinality(c, "exactly"),
+            DisplayComp::Not(ce) => {
+                w.keyword("not");
+                w.out.push(' ');
+                w.nested(ce.as_ref());
+            }
+            DisplayComp::Data(d) => {
+                w.nested(d.dp.as_ref());
+                w.infix("value");
+                w.typed_literal(&d.value);
+            }
+            DisplayComp::DataRange(dr) => dr.write_manchester(w),
+            DisplayComp::Chain(g) => w.join(&g.0, "o"),
+            DisplayComp::Literal(l) => w.typed_literal(l),
+            DisplayComp::Fact(r) => {
+                w.nested(r.rel.as_ref());
+                w.out.push(' ');
+                w.nested(r.ce.as_ref());
+            }
+            DisplayComp::Inverse(op) => {
+                w.keyword("inverse");
+                w.out.push(' ');
+                w.nested(op.as_ref());
+            }
+        }
+    }
+
+    fn is_atomic(&self) -> bool {
+        match self {
+            DisplayComp::Simple(_)
+            | DisplayComp::Anonymous(_)
+            | DisplayComp::OneOf(_)
+            | DisplayComp::Literal(_) => true,
+            // `inverse p` is a property expression, as in `inverse p exactly 1`.
+            DisplayComp::Inverse(op) => op.is_atomic(),
+            DisplayComp::DataRange(dr) => dr.is_atomic(),
+            _ => false,
+        }
+    }
+}
+
+impl Manchester for DataRangeDisplay {
+    fn write_manchester(&self, w: &mut Writer) {
+        match self {
+            DataRangeDisplay::Datatype(e) => w.entity(e),
+            DataRangeDisplay::And(drs) => w.join(drs, "and"),
+            DataRangeDisplay::Or(drs) => w.join(drs, "or"),
+            DataRangeDisplay::Not(dr) => {
+                w.keyword("not");
+                w.out.push(' ');
+                w.nested(dr.as_ref());
+            }
+            DataRangeDisplay::OneOf(ls) => w.one_of(ls, |w, l| w.typed_literal(l)),
+            DataRangeDisplay::Restriction(r) => {
+                w.entity(&r.datatype);
+                w.out.push('[');
+                for (i, f) in r.facets.iter().enumerate() {
+                    if i > 0 {
+                        w.out.push_str(", ");
+                    }
+                    w.facet(f, &r.datatype);
+                }
+                w.out.push(']');
+            }
+        }
+    }
+
+    fn is_atomic(&self) -> bool {
+        matches!(
+            self,
+            DataRangeDisplay::Datatype(_)
+                | DataRangeDisplay::OneOf(_)
+                | DataRangeDisplay::Restriction(_)
+        )
+    }
+}
+
+/// Canonical Manchester form of a literal of the XSD datatype `name`, for
+/// the datatypes that have one and values of their lexical space.
+fn bare_literal(value: &str, name: &str) -> Option<String> {
+    let digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    match name {
+        "integer" => {
+            let unsigned = value.strip_prefix(['+', '-']).unwrap_or(value);
+            (!unsigned.is_empty() && digits(unsigned)).then(|| value.to_string())
+        }
+        "decimal" => {
+            let unsigned = value.strip_prefix(['+', '-']).unwrap_or(value);
+            let sign = &value[..value.len() - unsigned.len()];
+            let (int, frac) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+            if unsigned.is_empty() || unsigned == "." || !digits(int) || !digits(frac) {
+                return None;
+            }
+            let or_zero = |s: &str| {
+                if s.is_empty() {
+                    "0".to_string()
+                } else {
+                    s.to_string()
+                }
+            };
+            Some(format!("{sign}{}.{}", or_zero(int), or_zero(frac)))
+        }
+        "float" => match value.parse::<f32>() {
+            // `INF` and `NaN` have no bare form.
+            Ok(f) if f.is_finite() => Some(format!("{f:?}f")),
+            _ => None,
+        },
+        "boolean" => match value {
+            "true" | "1" => Some("true".to_string()),
+            "false" | "0" => Some("false".to_string()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Labels with spaces or punctuation are quoted, as Protégé does.
+fn quote_name(name: &str) -> String {
+    if name
+        .chars()
+        .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | ':' | '.'))
+    {
+        name.to_string()
+    } else {
+        format!("'{name}'")
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entity(iri: &str, display: &str) -> EntityDisplay {
+        EntityDisplay {
+            iri: iri.to_string(),
+            identifier: display.to_string(),
+            display: display.to_string(),
+            url: String::new(),
+            deprecated: false,
+        }
+    }
+
+    fn literal(value: &str, datatype: &str) -> DisplayComp {
+        DisplayComp::Literal(LiteralDisplay {
+            value: value.to_string(),
+            datatype: Some(entity(
+                &format!("{XSD}{datatype}"),
+                &format!("xsd:{datatype}"),
+            )),
+            lang: None,
+        })
+    }
+
+    #[test]
+    fn inverse_properties_are_not_parenthesized() {
+        let inverse = DisplayComp::Exactly(CardDisplay {
+            n: 1,
+            rel: Box::new(DisplayComp::Inverse(Box::new(DisplayComp::Simple(entity(
+                "http://example.org/p",
+                "p",
+            ))))),
+            ce: Box::new(DisplayComp::Simple(entity("http://example.org/C", "C"))),
+        });
+        assert_eq!(inverse.manchester_text(), "inverse p exactly 1 C");
+    }
+
+    #[test]
+    fn literals_have_canonical_forms() {
+        let cases = [
+            (literal("1", "boolean"), "true"),
+            (literal("false", "boolean"), "false"),
+            (literal("1.5", "float"), "1.5f"),
+            (literal("3", "float"), "3.0f"),
+            (literal("1E3", "float"), "1000.0f"),
+            (literal("NaN", "float"), "\"NaN\"^^xsd:float"),
+            (literal("2", "decimal"), "2.0"),
+            (literal("-.5", "decimal"), "-0.5"),
+            (literal("+7", "integer"), "+7"),
+            (literal("x", "integer"), "\"x\"^^xsd:integer"),
+            (literal("a \"b\"", "string"), "\"a \\\"b\\\"\""),
+            (literal("2020-01-01", "date"), "\"2020-01-01\"^^xsd:date"),
+        ];
+        for (l, expected) in cases {
+            assert_eq!(l.manchester_text(), expected);
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use crate::config::RedirectFormat;
+use crate::export::ExportFormat;
+
+const HEADER: &str = "# Content negotiation rules generated by hyppo.";
+
+/// Rule files redirecting entity paths to their page or serialization, by
+/// the `Accept` header, as `(file name, content)`.
+///
+/// `pages` are the entity paths relative to the site root, as written by
+/// the build, and `base` the path of the site root. Formats are tried in the
+/// order of `ExportFormat::ALL`, and HTML is served otherwise.
+pub fn redirect_rules(
+    format: RedirectFormat,
+    base: &str,
+    pages: &[String],
+) -> Vec<(&'static str, String)> {
+    match format {
+        RedirectFormat::Apache => vec![(".htaccess", apache(base, pages))],
+        RedirectFormat::Nginx => vec![("redirects.nginx.conf", nginx(base, pages))],
+        RedirectFormat::Caddy => vec![("redirects.Caddyfile", caddy(base, pages))],
+        RedirectFormat::Netlify => vec![
+            ("_redirects", netlify_redirects(base, pages)),
+            ("_headers", netlify_headers(base)),
+        ],
+    }
+}
+
+fn apache(base: &str, pages: &[String]) -> String {
+    let mut out = vec![HEADER.to_string(), "Options -MultiViews".to_string()];
+    for f in ExportFormat::ALL {
+        out.push(format!("AddType {} .{}", f.media_type(), f.extension()));
+    }
+    out.push("<IfModule mod_headers.c>".to_string());
+    out.push("    Header always append Vary Accept".to_string());
+    out.push("</IfModule>".to_string());
+    out.push("RewriteEngine On".to_string());
+    out.push(format!("RewriteBase {base}"));
+    out.push("RewriteRule ^ - [E=HYPPO_EXT:html,E=HYPPO_ROOT:index.html]".to_string());
+    // Later matches override earlier ones, so the preferred format goes last.
+    for f in ExportFormat::ALL.iter().rev() {
+        out.push(format!(
+            "RewriteCond %{{HTTP_ACCEPT}} {}",
+            regex_escape(f.media_type())
+        ));
+        out.push(format!(
+            "RewriteRule ^ - [E=HYPPO_EXT:{0},E=HYPPO_ROOT:ontology.{0}]",
+            f.extension()
+        ));
+    }
+    out.push("RewriteRule ^$ %{ENV:HYPPO_ROOT} [R=303,L]".to_string());
+    for page in pages {
+        out.push(format!(
+            "RewriteRule ^{}$ {page}.%{{ENV:HYPPO_EXT}} [R=303,L]",
+            regex_escape(page)
+        ));
+    }
+    out.join("\n") + "\n"
+}
+
+fn nginx(base: &str, pages: &[String]) -> String {
+    let mut out = vec![
+        HEADER.to_string(),
+        "# Include in the server block.".to_string(),
+        "set $hyppo_ext html;".to_string(),
+        "set $hyppo_root index.html;".to_string(),
+    ];
+    // Later matches override earlier ones, so the preferred format goes last.
+    for f in ExportFormat::ALL.iter().rev() {
+        out.push(format!(
+            "if ($http_accept ~* \"{}\") {{",
+            regex_escape(f.media_type())
+        ));
+        out.push(format!("    set $hyppo_ext {};", f.extension()));
+        out.push(format!("    set $hyppo_root ontology.{};", f.extension()));
+        out.push("}".to_string());
+    }
+    for f in ExportFormat::ALL {
+        out.push(format!("location ~ \\.{}$ {{", f.extension()));
+        out.push("    types { }".to_string());
+        out.push(format!("    default_type {};", f.media_type()));
+        out.push("}".to_string());
+    }
+    out.push(format!("location = {base} {{"));
+    out.push("    add_header Vary Accept always;".to_string());
+    out.push(format!("    return 303 {base}$hyppo_root;"));
+    out.push("}".to_string());
+    for page in pages {
+        out.push(format!("location = {base}{page} {{"));
+        out.push("    add_header Vary Accept always;".to_string());
+        out.push(format!("    return 303 {base}{page}.$hyppo_ext;"));
+        out.push("}".to_string());
+    }
+    out.join("\n") + "\n"
+}
+
+fn caddy(base: &str, pages: &[String]) -> String {
+    let mut out = vec![
+        HEADER.to_string(),
+        "# Import in the site block.".to_string(),
+        "map {header.Accept} {hyppo_ext} {hyppo_root} {".to_string(),
+    ];
+    for f in ExportFormat::ALL {
+        out.push(format!(
+            "\t~{} {1} ontology.{1}",
+            regex_escape(f.media_type()),
+            f.extension()
+        ));
+    }
+    out.push("\tdefault html index.html".to_string());
+    out.push("}".to_string());
+    for f in ExportFormat::ALL {
+        out.push(format!(
+            "header *.{} Content-Type {}",
+            f.extension(),
+            f.media_type()
+        ));
+    }
+    out.push(format!("@hyppo_root path {base}"));
+    out.push("header @hyppo_root Vary Accept".to_string());
+    out.push(format!("redir @hyppo_root {base}{{hyppo_root}} 303"));
+    if !pages.is_empty() {
+        let paths: Vec<String> = pages.iter().map(|p| format!("{base}{p}")).collect();
+        out.push(format!("@hyppo_entities path {}", paths.join(" ")));
+        out.push("header @hyppo_entities Vary Accept".to_string());
+        out.push("redir @hyppo_entities {path}.{hyppo_ext} 303".to_string());
+    }
+    out.join("\n") + "\n"
+}
+
+/// Netlify cannot match on the `Accept` header, so entity paths always
+/// redirect to their page and serializations are only reachable by their
+/// extension.
+fn netlify_redirects(base: &str, pages: &[String]) -> String {
+    let mut out = vec![
+        HEADER.to_string(),
+        "# Netlify cannot match on Accept, serializations are linked by extension.".to_string(),
+    ];
+    for page in pages {
+        out.push(format!("{base}{page} {base}{page}.html 303"));
+    }
+    out.join("\n") + "\n"
+}
+
+fn netlify_headers(base: &str) -> String {
+    let mut out = vec![HEADER.to_string()];
+    for f in ExportFormat::ALL {
+        out.push(format!("{base}*.{}", f.extension()));
+        out.push(format!("  Content-Type: {}", f.media_type()));
+    }
+    out.join("\n") + "\n"
+}
+
+fn regex_escape(s: &str) -> String {
+    let mut out = String::new();
+    for c in s.chars() {
+        if "\\.^$|?*+()[]{}".contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
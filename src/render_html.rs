@@ -1,24 +1,42 @@
 use curie::PrefixMapping;
 use eyre::{Context, Result};
-use horned_owl::io::owx::reader::read_with_build;
 use horned_owl::model::{
-    AnnotatedComponent, AnnotationProperty, AnnotationSubject, AnnotationValue, ArcStr, Build, Class, ClassAssertion, ClassExpression, DataProperty, DataPropertyDomain, DataPropertyRange, DeclareAnnotationProperty, DeclareClass, DeclareDataProperty, DeclareNamedIndividual, DeclareObjectProperty, EquivalentClasses, Individual, InverseObjectProperties, Literal, NamedIndividual, ObjectProperty, ObjectPropertyDomain, ObjectPropertyExpression, ObjectPropertyRange, RcStr, SubClassOf, SubObjectPropertyExpression, SubObjectPropertyOf
+    AnnotatedComponent, Annotation, AnnotationProperty, AnnotationSubject, AnnotationValue, ArcStr,
+    Build, Class, ClassAssertion, ClassExpression, DataProperty, DataPropertyAssertion,
+    DataPropertyDomain, DataPropertyRange, DataRange, Datatype, DatatypeDefinition,
+    DeclareAnnotationProperty, DeclareClass, DeclareDatatype, DeclareNamedIndividual,
+    DeclareObjectProperty, DifferentIndividuals, DisjointClasses, DisjointDataProperties,
+    DisjointObjectProperties, DisjointUnion, EquivalentClasses, EquivalentDataProperties,
+    EquivalentObjectProperties, HasKey, Individual, InverseObjectProperties, Literal,
+    NamedIndividual, NegativeDataPropertyAssertion, NegativeObjectPropertyAssertion,
+    ObjectProperty, ObjectPropertyAssertion, ObjectPropertyDomain, ObjectPropertyExpression,
+    ObjectPropertyRange, PropertyExpression, RcStr, SameIndividual, SubAnnotationPropertyOf,
+    SubClassOf, SubDataPropertyOf, SubObjectPropertyExpression, SubObjectPropertyOf,
 };
 use horned_owl::model::{Component, ComponentKind, ForIRI, IRI};
 use horned_owl::ontology::indexed::ForIndex;
 use horned_owl::ontology::iri_mapped::IRIMappedOntology;
+use horned_owl::vocab::Facet;
 use serde::Serialize;
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::btree_map;
 use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fmt::{self, Debug};
-use std::fs::File;
-use std::io::BufReader;
 use std::rc::Rc;
 use std::sync::Arc;
 use tera::Context as TeraContext;
 use tera::Tera;
 
-use crate::config::{OntologyConfig, Settings};
+use crate::config::{
+    AnnotationRole, EntityOrder, ExpressionSyntax, OntologyConfig, Settings, parser_config,
+};
+
+use crate::export::{ExportFormat, write_components};
+use crate::loader::{Resolver, read_imports_closure, read_ontology};
+use crate::manchester::Manchester;
+use crate::search::{SearchEntry, SearchIndex};
+use crate::usage::{UsageIndex, axiom_subjects};
 
 #[derive(Debug, Clone)]
 pub struct RenderError(String);
@@ -36,14 +54,114 @@ enum Kind {
     AnnotationProperty,
     NamedIndividual,
     Undefined,
-    DataProperty
+    DataProperty,
+    Datatype,
 }
 
-#[derive(Serialize)]
-struct OntologyAnnotation {
-    iri: String,
-    display: String,
-    value: String,
+impl Kind {
+    fn of_declaration(kind: ComponentKind) -> Kind {
+        match kind {
+            ComponentKind::DeclareClass => Kind::Class,
+            ComponentKind::DeclareObjectProperty => Kind::ObjectProperty,
+            ComponentKind::DeclareAnnotationProperty => Kind::AnnotationProperty,
+            ComponentKind::DeclareNamedIndividual => Kind::NamedIndividual,
+            ComponentKind::DeclareDataProperty => Kind::DataProperty,
+            ComponentKind::DeclareDatatype => Kind::Datatype,
+            _ => Kind::Undefined,
+        }
+    }
+
+    /// Name of the kind in the JSON documents.
+    fn name(&self) -> &'static str {
+        match self {
+            Kind::Class => "class",
+            Kind::ObjectProperty => "object-property",
+            Kind::AnnotationProperty => "annotation-property",
+            Kind::NamedIndividual => "named-individual",
+            Kind::Undefined => "undefined",
+            Kind::DataProperty => "data-property",
+            Kind::Datatype => "datatype",
+        }
+    }
+}
+
+/// Declaration kinds of the entities that get a page.
+pub const DECLARATION_KINDS: [ComponentKind; 6] = [
+    ComponentKind::DeclareClass,
+    ComponentKind::DeclareNamedIndividual,
+    ComponentKind::DeclareDataProperty,
+    ComponentKind::DeclareObjectProperty,
+    ComponentKind::DeclareAnnotationProperty,
+    ComponentKind::DeclareDatatype,
+];
+
+/// Version of the JSON documents written by `build` and served by `serve`,
+/// increased on incompatible changes. See `docs/json-api.md`.
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+/// Language preferred when none is configured, which is also the `lang` of
+/// the pages.
+const DEFAULT_LANGUAGE: &str = "en";
+
+/// Keys of the page context left out of the JSON documents of entities.
+const PAGE_ONLY_KEYS: [&str; 5] = ["base", "title", "language", "languages", "exports"];
+
+/// An entity in `entities.json`.
+#[derive(Serialize, Debug)]
+pub struct CatalogueEntry {
+    pub iri: String,
+    pub curie: String,
+    pub label: Option<String>,
+    pub kind: &'static str,
+    /// Asserted named superclasses, superproperties or types.
+    pub parents: Vec<String>,
+    pub deprecated: bool,
+}
+
+/// The `entities.json` document.
+#[derive(Serialize, Debug)]
+pub struct EntityCatalogue {
+    pub schema_version: u32,
+    pub entities: Vec<CatalogueEntry>,
+}
+
+/// Property characteristics, in the order Protégé lists them.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Characteristic {
+    Functional,
+    #[serde(rename = "Inverse functional")]
+    InverseFunctional,
+    Transitive,
+    Symmetric,
+    Asymmetric,
+    Reflexive,
+    Irreflexive,
+}
+
+/// An annotation, with the annotations on the axiom that states it.
+#[derive(Serialize, Debug)]
+pub struct OntologyAnnotation {
+    pub iri: String,
+    pub display: String,
+    pub value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+    pub annotations: Vec<OntologyAnnotation>,
+}
+
+/// Link to a serialization of an entity or of the ontology.
+#[derive(Serialize, Debug)]
+pub struct ExportLink {
+    pub format: &'static str,
+    pub url: String,
+}
+
+/// Link to the same page in another language site.
+#[derive(Serialize, Debug)]
+pub struct LanguageLink {
+    pub lang: String,
+    pub url: String,
+    pub current: bool,
 }
 
 #[derive(Serialize, Debug)]
@@ -51,33 +169,85 @@ pub struct EntityDisplay {
     pub iri: String,
     pub identifier: String,
     pub display: String,
+    /// Link to the entity page, or to its section in single page builds.
+    pub url: String,
+    pub deprecated: bool,
 }
 
 #[derive(Serialize, Debug)]
-pub struct GroupDisplay(Vec<DisplayComp>);
+pub struct GroupDisplay(pub Vec<DisplayComp>);
 
 #[derive(Serialize, Debug)]
 pub struct RelDisplay {
-    rel: Box<DisplayComp>,
-    ce: Box<DisplayComp>,
+    pub rel: Box<DisplayComp>,
+    pub ce: Box<DisplayComp>,
 }
 
 #[derive(Serialize, Debug)]
 pub struct DPDisplay {
-    dp: Box<DisplayComp>,
-    value: String,
+    pub dp: Box<DisplayComp>,
+    pub value: LiteralDisplay,
+}
+
+#[derive(Serialize, Debug)]
+pub struct CardDisplay {
+    pub n: u32,
+    pub rel: Box<DisplayComp>,
+    pub ce: Box<DisplayComp>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct LiteralDisplay {
+    pub value: String,
+    pub datatype: Option<EntityDisplay>,
+    pub lang: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct FacetDisplay {
+    pub facet: String,
+    pub value: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct RestrictionDisplay {
+    pub datatype: EntityDisplay,
+    pub facets: Vec<FacetDisplay>,
+}
+
+#[derive(Serialize, Debug)]
+pub enum DataRangeDisplay {
+    Datatype(EntityDisplay),
+    And(Vec<DataRangeDisplay>),
+    Or(Vec<DataRangeDisplay>),
+    Not(Box<DataRangeDisplay>),
+    OneOf(Vec<LiteralDisplay>),
+    Restriction(RestrictionDisplay),
 }
 
 #[derive(Serialize, Debug)]
 pub enum DisplayComp {
     Simple(EntityDisplay),
+    Anonymous(String),
     And(GroupDisplay),
     Or(GroupDisplay),
+    OneOf(GroupDisplay),
     Some(RelDisplay),
     Value(RelDisplay),
     All(RelDisplay),
+    Self_(Box<DisplayComp>),
+    Min(CardDisplay),
+    Max(CardDisplay),
+    Exactly(CardDisplay),
     Not(Box<DisplayComp>),
     Data(DPDisplay),
+    DataSome(RelDisplay),
+    DataAll(RelDisplay),
+    DataRange(DataRangeDisplay),
+    Chain(GroupDisplay),
+    Inverse(Box<DisplayComp>),
+    Literal(LiteralDisplay),
+    Fact(RelDisplay),
 }
 
 impl EntityDisplay {
@@ -86,29 +256,71 @@ impl EntityDisplay {
             iri,
             identifier,
             display,
+            url: String::new(),
+            deprecated: false,
         }
     }
 }
 
+/// A class expression or data range as shown on entity pages. The Manchester
+/// syntax renderings are only filled in when selected in the settings,
+/// otherwise templates expand the structure with their own macros.
+#[derive(Serialize, Debug)]
+pub struct ExpressionDisplay<T> {
+    #[serde(flatten)]
+    pub expression: T,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub manchester: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// Annotations on the axiom the expression comes from.
+    pub annotations: Vec<OntologyAnnotation>,
+}
+
+/// A property assertion on an individual page. For incoming assertions the
+/// value is the subject of the assertion.
+#[derive(Serialize, Debug)]
+pub struct FactDisplay {
+    pub property: ExpressionDisplay<DisplayComp>,
+    pub value: ExpressionDisplay<DisplayComp>,
+    pub negative: bool,
+    pub annotations: Vec<OntologyAnnotation>,
+}
+
+/// An axiom using an entity, listed in the "Usage" section of its page.
+#[derive(Serialize, Debug)]
+pub struct UsageDisplay {
+    pub subject: ExpressionDisplay<DisplayComp>,
+    pub keyword: &'static str,
+    pub objects: Vec<ExpressionDisplay<DisplayComp>>,
+    pub annotations: Vec<OntologyAnnotation>,
+}
+
+/// Members of an n-ary axiom, such as the classes of a disjoint union.
 #[derive(Serialize, Debug)]
+pub struct MembersDisplay {
+    pub members: Vec<ExpressionDisplay<DisplayComp>>,
+    pub annotations: Vec<OntologyAnnotation>,
+}
+
+/// A class and its asserted subclasses.
+#[derive(Serialize, Debug)]
+pub struct TreeNode {
+    #[serde(flatten)]
+    pub entity: EntityDisplay,
+    pub children: Vec<TreeNode>,
+}
+
+#[derive(Serialize, Debug, Default)]
 pub struct SideBar {
     classes: Vec<EntityDisplay>,
+    class_tree: Vec<TreeNode>,
     named_individuals: Vec<EntityDisplay>,
     annotation_props: Vec<EntityDisplay>,
     data_props: Vec<EntityDisplay>,
     object_props: Vec<EntityDisplay>,
-}
-
-impl Default for SideBar {
-    fn default() -> Self {
-        SideBar {
-            classes: vec![],
-            named_individuals: vec![],
-            annotation_props: vec![],
-            data_props: vec![],
-            object_props: vec![],
-        }
-    }
+    datatypes: Vec<EntityDisplay>,
+    obsolete: Vec<EntityDisplay>,
 }
 
 pub trait IRIMappedRenderHTML<A: ForIRI> {
@@ -116,12 +328,15 @@ pub trait IRIMappedRenderHTML<A: ForIRI> {
         Err(eyre::Report::msg("Not implemented"))
     }
 
-    fn render_all_declarations_html(&mut self) -> Result<HashMap<IRI<A>, String>> {
+    fn render_all_declarations_html(&mut self) -> Result<BTreeMap<IRI<A>, String>> {
         Err(eyre::Report::msg("Not implemented"))
     }
     fn render_metadata_html(&mut self, _: Option<&OntologyConfig>) -> Result<String> {
         Err(eyre::Report::msg("Not implemented"))
     }
+    fn render_single_page_html(&mut self) -> Result<String> {
+        Err(eyre::Report::msg("Not implemented"))
+    }
 
     fn get_iris_for_declaration(&mut self, _: ComponentKind) -> Vec<IRI<A>> {
         vec![]
@@ -129,6 +344,24 @@ pub trait IRIMappedRenderHTML<A: ForIRI> {
     fn collect_entity_tree(&mut self, _: &OntologyConfig) -> Result<SideBar> {
         Err(eyre::Report::msg("Error when rendering tree"))
     }
+    fn render_tree_json(&mut self, _: Option<&OntologyConfig>) -> Result<String> {
+        Err(eyre::Report::msg("Not implemented"))
+    }
+    fn render_declaration_iri_export(&mut self, _: &IRI<A>, _: ExportFormat) -> Result<Vec<u8>> {
+        Err(eyre::Report::msg("Not implemented"))
+    }
+    fn render_ontology_export(&mut self, _: ExportFormat) -> Result<Vec<u8>> {
+        Err(eyre::Report::msg("Not implemented"))
+    }
+    fn render_declaration_iri_json(&mut self, _: &IRI<A>) -> Result<String> {
+        Err(eyre::Report::msg("Not implemented"))
+    }
+    fn render_entities_json(&mut self) -> Result<String> {
+        Err(eyre::Report::msg("Not implemented"))
+    }
+    fn render_search_json(&mut self) -> Result<String> {
+        Err(eyre::Report::msg("Not implemented"))
+    }
 
     fn build_entity_display(&self, _: IRI<A>) -> EntityDisplay {
         todo!("build_entity_display has to be implemented")
@@ -141,275 +374,53 @@ pub trait IRIMappedRenderHTML<A: ForIRI> {
     fn unpack_object_property_expression(&self, _: ObjectPropertyExpression<A>) -> DisplayComp {
         todo!()
     }
+
+    fn unpack_individual(&self, individual: Individual<A>) -> DisplayComp;
+
+    fn unpack_data_range(&self, dr: DataRange<A>) -> DataRangeDisplay;
+
+    fn build_literal_display(&self, l: Literal<A>) -> LiteralDisplay;
 }
 
 pub struct OntologyRender<A: ForIRI, AA: ForIndex<A>> {
     pub ontology: IRIMappedOntology<A, AA>,
+    pub imports: Vec<IRIMappedOntology<A, AA>>,
     pub prefix_mapping: PrefixMapping,
-    pub label_map: HashMap<IRI<A>, String>,
+    pub label_map: LabelMap<A>,
+    pub deprecated: HashSet<IRI<A>>,
+    /// Entities declared in the ontology or its imports closure.
+    pub declared: HashSet<IRI<A>>,
+    /// Position of each declared entity in the source documents.
+    pub document_order: HashMap<String, usize>,
     pub settings: Settings,
+    /// Language of the site being built, when building one site per language.
+    pub language: Option<String>,
     pub templates: Tera,
+    pub usage: UsageIndex<A>,
 }
 
+/// Labels of every entity, keyed by language tag.
+pub type LabelMap<A> = HashMap<IRI<A>, HashMap<Option<String>, String>>;
+
+/// Annotations of a role keyed by language tag, with the rank of their
+/// property, see [`keep_preferred`].
+type RankedAnnotations = HashMap<Option<String>, (usize, OntologyAnnotation)>;
+
 pub type RcOntologyRender = OntologyRender<RcStr, Rc<AnnotatedComponent<RcStr>>>;
 pub type ArcOntologyRender = OntologyRender<ArcStr, Arc<AnnotatedComponent<ArcStr>>>;
 
 impl<A: ForIRI, AA: ForIndex<A>> IRIMappedRenderHTML<A> for OntologyRender<A, AA> {
     fn render_declaration_iri_html(&mut self, iri: &IRI<A>) -> Result<String> {
-        let mut context = TeraContext::new();
-        let mut annotations: Vec<OntologyAnnotation> = vec![];
-        let mut this_kind: Kind = Kind::Undefined;
-        let mut super_entities: Vec<DisplayComp> = vec![];
-        let mut inverse_ops: Vec<DisplayComp> = vec![];
-        let mut sub_entities: Vec<DisplayComp> = vec![];
-        let mut equivalent_classes: Vec<DisplayComp> = vec![];
-        let mut class_assertions: Vec<DisplayComp> = vec![];
-        context.insert(
-            "base",
-            &self.settings.baseurl.clone().unwrap_or(String::from("/")),
-        );
-        let anns: Vec<AnnotatedComponent<A>> = self
-            .ontology
-            .components_for_iri(&iri)
-            .map(|x| x.clone())
-            .collect();
-        for ann_cmp in anns {
-            let _ann = &ann_cmp.ann; // May add annotations later
-            let cmp = &ann_cmp.component;
-            match cmp {
-                Component::DeclareClass(dc) => {
-                    context.insert("iri", dc.0.0.as_ref());
-                    this_kind = Kind::Class;
-                    context.insert("kind", "klss")
-                }
-                Component::DeclareObjectProperty(op) => {
-                    context.insert("iri", op.0.0.as_ref());
-                    this_kind = Kind::ObjectProperty;
-                    context.insert("kind", "object-property")
-                }
-                Component::DeclareAnnotationProperty(ap) => {
-                    context.insert("iri", ap.0.0.as_ref());
-                    this_kind = Kind::AnnotationProperty;
-                    context.insert("kind", "annotation-property")
-                }
-                Component::DeclareNamedIndividual(ni) => {
-                    context.insert("iri", ni.0.0.as_ref());
-                    this_kind = Kind::NamedIndividual;
-                    context.insert("kind", "named-individual")
-                }
-                Component::DeclareDataProperty(dp) => {
-                    context.insert("iri", dp.0.0.as_ref());
-                    this_kind = Kind::DataProperty;
-                    context.insert("kind", "data-property")
-                }
-                Component::AnnotationAssertion(aa) => match aa.ann.ap.0.as_ref() {
-                    "http://www.w3.org/2000/01/rdf-schema#label" => context.insert(
-                        "label",
-                        &unpack_annotation_value(&aa.ann.av).unwrap_or(iri.to_string()),
-                    ),
-                    "http://www.w3.org/2004/02/skos/core#definition" => context.insert(
-                        "definition",
-                        &unpack_annotation_value(&aa.ann.av).unwrap_or(iri.to_string()),
-                    ),
-                    "http://www.w3.org/2004/02/skos/core#example" => context.insert(
-                        "example",
-                        &unpack_annotation_value(&aa.ann.av).unwrap_or(iri.to_string()),
-                    ),
-                    _ => match unpack_annotation_value(&aa.ann.av) {
-                        Some(vv) => {
-                            let label = match self.prefix_mapping.shrink_iri(aa.ann.ap.0.as_ref()) {
-                                Ok(s) => s.into(),
-                                Err(_) => aa.ann.ap.0.to_string(),
-                            };
-                            let annotation = OntologyAnnotation {
-                                iri: aa.ann.ap.0.to_string(),
-                                display: label,
-                                value: vv,
-                            };
-                            annotations.push(annotation);
-                        }
-                        None => (),
-                    },
-                },
-                Component::SubClassOf(SubClassOf {
-                    sup: ClassExpression::Class(spc),
-                    sub: ClassExpression::Class(subc),
-                }) => {
-                    if &spc.0 == iri {
-                        let child_display = self.build_entity_display(subc.0.clone());
-                        sub_entities.push(DisplayComp::Simple(child_display))
-                    } else if &subc.0 == iri {
-                        let parent_display = self.build_entity_display(spc.0.clone());
-                        super_entities.push(DisplayComp::Simple(parent_display));
-                    }
-                }
-                Component::SubClassOf(SubClassOf {
-                    sup,
-                    sub: ClassExpression::Class(subc),
-                }) => {
-                    if &subc.0 == iri {
-                        let class_display = self.unpack_class_expression(sup.clone());
-                        super_entities.push(class_display);
-                    }
-                }
-                Component::SubClassOf(SubClassOf {
-                    sup: ClassExpression::Class(supc),
-                    sub,
-                }) => {
-                    if &supc.0 == iri {
-                        let class_display = self.unpack_class_expression(sub.clone());
-                        sub_entities.push(class_display);
-                    }
-                }
-                Component::SubObjectPropertyOf(SubObjectPropertyOf {
-                    sup: ObjectPropertyExpression::ObjectProperty(sup),
-                    sub:
-                        SubObjectPropertyExpression::ObjectPropertyExpression(
-                            ObjectPropertyExpression::ObjectProperty(sub),
-                        ),
-                }) => {
-                    if &sup.0 == iri {
-                        let child_display = self.build_entity_display(sub.0.clone());
-                        sub_entities.push(DisplayComp::Simple(child_display))
-                    } else if &sup.0 == iri {
-                        let parent_display = self.build_entity_display(sub.0.clone());
-                        super_entities.push(DisplayComp::Simple(parent_display));
-                    }
-                }
-                Component::SubDataPropertyOf(_) => (),
-                Component::EquivalentClasses(EquivalentClasses(ecs)) => {
-                    let ecx: Vec<DisplayComp> = ecs
-                        .iter()
-                        .map(|e| self.unpack_class_expression(e.clone()))
-                        .filter(|ex| {
-                            if let DisplayComp::Simple(e) = ex {
-                                !(&e.iri == iri.as_ref())
-                            } else {
-                                true
-                            }
-                        })
-                        .collect();
-                    equivalent_classes.extend(ecx)
-                }
-                Component::EquivalentObjectProperties(_) => (),
-                Component::EquivalentDataProperties(_) => (),
-                Component::InverseObjectProperties(InverseObjectProperties(iop, iiop)) => {
-                    if &iop.0 == iri {
-                        let op_display = self.build_entity_display(iiop.0.clone());
-                        inverse_ops.push(DisplayComp::Simple(op_display));
-                    } else if &iiop.0 == iri {
-                        let op_display = self.build_entity_display(iop.0.clone());
-                        inverse_ops.push(DisplayComp::Simple(op_display));
-                    }
-                }
-                Component::ObjectPropertyRange(ObjectPropertyRange {
-                    ope: ObjectPropertyExpression::ObjectProperty(ObjectProperty(ii)),
-                    ce,
-                }) => {
-                    if ii == iri {
-                        let ce_display = self.unpack_class_expression(ce.clone());
-                        context.insert("op_range", &ce_display);
-                    }
-                }
-                Component::ObjectPropertyDomain(ObjectPropertyDomain {
-                    ope: ObjectPropertyExpression::ObjectProperty(ObjectProperty(ii)),
-                    ce,
-                }) => {
-                    if ii == iri {
-                        let ce_display = self.unpack_class_expression(ce.clone());
-                        context.insert("op_domain", &ce_display);
-                    }
-                }
-                Component::DataPropertyRange(DataPropertyRange {
-                    dp: DataProperty(ii),
-                    dr,
-                }) => {
-                    if ii == iri {
-                        ()
-                    }
-                }
-                Component::DataPropertyDomain(DataPropertyDomain {
-                    dp: DataProperty(ii),
-                    ce,
-                }) => {
-                    if ii == iri {
-                        let ce_display = self.unpack_class_expression(ce.clone());
-                        context.insert("op_domain", &ce_display);
-                    }
-                }
-                Component::DisjointClasses(_) => (),
-                Component::DisjointObjectProperties(_) => (),
-                Component::DisjointDataProperties(_) => (),
-                Component::AnnotationPropertyRange(_) => (),
-                Component::AnnotationPropertyDomain(_) => (),
-                Component::ClassAssertion(ClassAssertion {
-                    ce,
-                    i: Individual::Named(ind),
-                }) => {
-                    if &ind.0 == iri {
-                        let cexp = self.unpack_class_expression(ce.clone());
-                        class_assertions.push(cexp);
-                    }
-                }
-                _ => (),
-            }
-        }
-        if super_entities.len() > 0 {
-            context.insert("super_classes", &super_entities);
-        }
-        if sub_entities.len() > 0 {
-            context.insert("sub_classes", &sub_entities);
-        }
-        if inverse_ops.len() > 0 {
-            context.insert("inverse_ops", &inverse_ops);
-        }
-        if equivalent_classes.len() > 0 {
-            context.insert("equivalent_classes", &equivalent_classes);
-        }
-        if class_assertions.len() > 0 {
-            context.insert("class_assertions", &class_assertions);
-        }
-        context.insert("annotations", &annotations);
-        let s = if let Some(f) = &self.settings.ontology.suffix {
-            f
-        } else {
-            &self.settings.ontology.iri
-        };
-        context.insert("title", &s);
-        match this_kind {
-            Kind::Class => self
-                .templates
-                .render("entity.html", &context)
-                .wrap_err("Could not render class page"),
-            Kind::ObjectProperty => self
-                .templates
-                .render("entity.html", &context)
-                .wrap_err("Could not render object property page"),
-            Kind::AnnotationProperty => self
-                .templates
-                .render("entity.html", &context)
-                .wrap_err("Could not render ann prop page"),
-            Kind::NamedIndividual => self
-                .templates
-                .render("entity.html", &context)
-                .wrap_err("Could not render ann prop page"),
-            Kind::DataProperty => self
-                .templates
-                .render("entity.html", &context)
-                .wrap_err("Could not render ann prop page"),
-            Kind::Undefined => {
-                Err(tera::Error::msg("Not implemented")).wrap_err("Unkown entity kind")
-            }
-        }
+        self.render_entity(iri, "entity.html")
     }
 
-    fn render_all_declarations_html(&mut self) -> Result<HashMap<IRI<A>, String>> {
-        let mut declaration_hm: HashMap<IRI<A>, String> = HashMap::new();
+    fn render_all_declarations_html(&mut self) -> Result<BTreeMap<IRI<A>, String>> {
+        let mut declaration_hm: BTreeMap<IRI<A>, String> = BTreeMap::new();
         for cl in self.get_iris_for_declaration(ComponentKind::DeclareClass) {
             let rendered_page = self.render_declaration_iri_html(&cl)?;
             match declaration_hm.entry(cl) {
-                Entry::Occupied(o) => println!("{:?}", o),
-                Entry::Vacant(v) => {
+                btree_map::Entry::Occupied(o) => println!("{:?}", o),
+                btree_map::Entry::Vacant(v) => {
                     v.insert(rendered_page);
                 }
             }
@@ -417,8 +428,8 @@ impl<A: ForIRI, AA: ForIndex<A>> IRIMappedRenderHTML<A> for OntologyRender<A, AA
         for ni in self.get_iris_for_declaration(ComponentKind::DeclareNamedIndividual) {
             let rendered_page = self.render_declaration_iri_html(&ni)?;
             match declaration_hm.entry(ni) {
-                Entry::Occupied(o) => println!("{:?}", o),
-                Entry::Vacant(v) => {
+                btree_map::Entry::Occupied(o) => println!("{:?}", o),
+                btree_map::Entry::Vacant(v) => {
                     v.insert(rendered_page);
                 }
             }
@@ -426,8 +437,8 @@ impl<A: ForIRI, AA: ForIndex<A>> IRIMappedRenderHTML<A> for OntologyRender<A, AA
         for dp in self.get_iris_for_declaration(ComponentKind::DeclareDataProperty) {
             let rendered_page = self.render_declaration_iri_html(&dp)?;
             match declaration_hm.entry(dp) {
-                Entry::Occupied(o) => println!("{:?}", o),
-                Entry::Vacant(v) => {
+                btree_map::Entry::Occupied(o) => println!("{:?}", o),
+                btree_map::Entry::Vacant(v) => {
                     v.insert(rendered_page);
                 }
             }
@@ -435,8 +446,8 @@ impl<A: ForIRI, AA: ForIndex<A>> IRIMappedRenderHTML<A> for OntologyRender<A, AA
         for op in self.get_iris_for_declaration(ComponentKind::DeclareObjectProperty) {
             let rendered_page = self.render_declaration_iri_html(&op)?;
             match declaration_hm.entry(op) {
-                Entry::Occupied(o) => println!("{:?}", o),
-                Entry::Vacant(v) => {
+                btree_map::Entry::Occupied(o) => println!("{:?}", o),
+                btree_map::Entry::Vacant(v) => {
                     v.insert(rendered_page);
                 }
             }
@@ -444,8 +455,18 @@ impl<A: ForIRI, AA: ForIndex<A>> IRIMappedRenderHTML<A> for OntologyRender<A, AA
         for ap in self.get_iris_for_declaration(ComponentKind::DeclareAnnotationProperty) {
             let rendered_page = self.render_declaration_iri_html(&ap)?;
             match declaration_hm.entry(ap) {
-                Entry::Occupied(o) => println!("{:?}", o),
-                Entry::Vacant(v) => {
+                btree_map::Entry::Occupied(o) => println!("{:?}", o),
+                btree_map::Entry::Vacant(v) => {
+                    v.insert(rendered_page);
+                }
+            }
+        }
+        for dt in self.get_iris_for_declaration(ComponentKind::DeclareDatatype) {
+            let rendered_page = self.render_declaration_iri_html(&dt)?;
+            match declaration_hm.entry(dt) {
+                // Also declared with another kind, its page is already rendered.
+                btree_map::Entry::Occupied(_) => {}
+                btree_map::Entry::Vacant(v) => {
                     v.insert(rendered_page);
                 }
             }
@@ -454,235 +475,245 @@ impl<A: ForIRI, AA: ForIndex<A>> IRIMappedRenderHTML<A> for OntologyRender<A, AA
     }
 
     fn get_iris_for_declaration(&mut self, component_kind: ComponentKind) -> Vec<IRI<A>> {
-        self.ontology
-            .component_for_kind(component_kind)
-            .map(|dc| match &dc.component {
+        let mut seen = HashSet::new();
+        self.closure_components_for_kind(component_kind)
+            .iter()
+            .filter_map(|dc| match &dc.component {
                 Component::DeclareClass(dc) => Some(dc.0.0.clone()),
                 Component::DeclareNamedIndividual(ni) => Some(ni.0.0.clone()),
                 Component::DeclareDataProperty(ddp) => Some(ddp.0.0.clone()),
                 Component::DeclareObjectProperty(dop) => Some(dop.0.0.clone()),
                 Component::DeclareAnnotationProperty(dap) => Some(dap.0.0.clone()),
+                Component::DeclareDatatype(ddt) => Some(ddt.0.0.clone()),
                 _ => None,
             })
-            .filter(|x| match x {
-                Some(_) => true,
-                None => false,
-            })
-            .map(|y| y.unwrap())
+            .filter(|iri| seen.insert(iri.clone()))
             .collect()
     }
 
     fn render_metadata_html(&mut self, base: Option<&OntologyConfig>) -> Result<String> {
-        let b = match base {
-            Some(s) => s,
-            None => &self.settings.ontology.clone(),
-        };
-        let mut context = TeraContext::default();
-        context.insert(
-            "base",
-            &self.settings.baseurl.clone().unwrap_or(String::from("/")),
-        );
-        if b.iri == self.settings.ontology.iri {
-            let mut contributors: Vec<OntologyAnnotation> = vec![];
-            let mut annotations: Vec<OntologyAnnotation> = vec![];
-            for oid in self.ontology.component_for_kind(ComponentKind::OntologyID) {
-                match &oid.component {
-                    Component::OntologyID(oi) => {
-                        match &oi.viri {
-                            Some(i) => context.insert("version", i.as_ref()),
-                            None => (),
-                        }
-                        match &oi.iri {
-                            Some(i) => context.insert("iri", i.as_ref()),
-                            None => (),
-                        }
-                    }
-                    _ => (),
-                }
-            }
-            for oann in self
-                .ontology
-                .component_for_kind(ComponentKind::OntologyAnnotation)
-            {
-                if let Component::OntologyAnnotation(oa) = &oann.component {
-                    let ann = match unpack_annotation_value(&oa.0.av) {
-                        Some(vv) => {
-                            let label = match self.prefix_mapping.shrink_iri(oa.0.ap.0.as_ref()) {
-                                Ok(s) => s.into(),
-                                Err(_) => oa.0.ap.0.to_string(),
-                            };
-                            let annotation = OntologyAnnotation {
-                                iri: oa.0.ap.0.to_string(),
-                                display: label,
-                                value: vv,
-                            };
-                            Some(annotation)
-                        }
-                        None => None,
-                    };
-                    match ann {
-                        Some(aa) => match oa.0.ap.0.underlying().as_ref() {
-                            "http://purl.org/dc/elements/1.1/contributor" => contributors.push(aa),
-                            "http://purl.org/dc/terms/title" => context.insert("title", &aa.value),
-                            "http://purl.org/dc/elements/1.1/license" => {
-                                context.insert("license", &aa.value)
-                            }
-                            "http://purl.org/dc/terms/license" => {
-                                context.insert("license", &aa.value)
-                            }
-                            "http://purl.org/dc/terms/description" => {
-                                context.insert("description", &aa.value)
-                            }
-                            _ => annotations.push(aa),
-                        },
-                        None => (),
-                    }
-                } else {
-                }
-            }
-            context.insert("annotations", &annotations);
-            context.insert("contributors", &contributors);
-        }
+        let context = self.metadata_context(base)?;
+        Ok(self.templates.render("ontology.html", &context)?)
+    }
 
-        let mut ontology_index: Vec<EntityDisplay> = vec![];
-        let s = if let Some(f) = &self.settings.title {
-            f
-        } else {
-            "Ontology Viewer"
-        };
-        context.insert("title", s);
-        ontology_index.push(EntityDisplay {
-            iri: self.settings.ontology.iri.to_string(),
-            identifier: String::new(),
-            display: if let Some(f) = &self.settings.ontology.suffix {
-                f.clone()
-            } else {
-                String::from("Base")
-            },
-        });
-        if let Some(v) = &self.settings.import {
-            for vc in v.iter() {
-                if let Some(e) = &vc.suffix {
-                    ontology_index.push(EntityDisplay {
-                        iri: vc.iri.clone(),
-                        display: e.clone(),
-                        identifier: e.clone(),
-                    })
-                }
+    fn render_single_page_html(&mut self) -> Result<String> {
+        let mut context = self.metadata_context(None)?;
+        let mut fragments: HashMap<String, String> = HashMap::new();
+        for kind in DECLARATION_KINDS {
+            for iri in self.get_iris_for_declaration(kind) {
+                let fragment = self.render_entity(&iri, "entity_content.html")?;
+                fragments.insert(iri.to_string(), fragment);
             }
         }
-        context.insert("ontology_index", &ontology_index);
-        let entity_tree = match self.collect_entity_tree(b) {
-            Ok(sb) => sb,
-            Err(e) => {
-                return Err(eyre::Report::msg(format!(
-                    "Failed to collect entities for sidebar, context: {}",
-                    e
-                )));
-            }
-        };
-        context.insert("sidebar", &entity_tree);
+        context.insert("single_page", &true);
+        context.insert("fragments", &fragments);
+        if let Some(assets) = &self.settings.assets
+            && let Ok(css) = std::fs::read_to_string(format!("{assets}/styles/base.css"))
+        {
+            context.insert("inline_css", &css);
+        }
         Ok(self.templates.render("ontology.html", &context)?)
     }
 
     fn collect_entity_tree(&mut self, base: &OntologyConfig) -> Result<SideBar> {
         let mut side_bar = SideBar::default();
-        let scos: Vec<AnnotatedComponent<A>> = self
-            .ontology
-            .component_for_kind(ComponentKind::DeclareClass)
-            .map(|x| x.clone())
-            .collect();
+        let mut tree_classes: Vec<IRI<A>> = vec![];
+        let scos: Vec<AnnotatedComponent<A>> =
+            self.closure_components_for_kind(ComponentKind::DeclareClass);
         for sco in scos {
-            match &sco.component {
-                Component::DeclareClass(DeclareClass(Class(ii))) => {
-                    if ii.contains(&base.iri) {
-                        let class_display = self.build_entity_display(ii.clone());
-                        side_bar.classes.push(class_display)
-                    }
+            if let Component::DeclareClass(DeclareClass(Class(ii))) = &sco.component
+                && ii.contains(&base.iri)
+            {
+                let class_display = self.build_entity_display(ii.clone());
+                if !class_display.deprecated {
+                    tree_classes.push(ii.clone());
                 }
-                _ => (),
+                side_bar.classes.push(class_display)
             }
         }
-        let niss: Vec<AnnotatedComponent<A>> = self
-            .ontology
-            .component_for_kind(ComponentKind::DeclareNamedIndividual)
-            .map(|x| x.clone())
-            .collect();
+        side_bar.class_tree = self.class_tree(&tree_classes);
+        let niss: Vec<AnnotatedComponent<A>> =
+            self.closure_components_for_kind(ComponentKind::DeclareNamedIndividual);
         for nis in niss {
-            match &nis.component {
-                Component::DeclareNamedIndividual(DeclareNamedIndividual(NamedIndividual(ii))) => {
-                    if ii.contains(&base.iri) {
-                        let i_display = self.build_entity_display(ii.clone());
-                        side_bar.named_individuals.push(i_display)
-                    }
-                }
-                _ => (),
+            if let Component::DeclareNamedIndividual(DeclareNamedIndividual(NamedIndividual(ii))) =
+                &nis.component
+                && ii.contains(&base.iri)
+            {
+                let i_display = self.build_entity_display(ii.clone());
+                side_bar.named_individuals.push(i_display)
             }
         }
-        let dops: Vec<AnnotatedComponent<A>> = self
-            .ontology
-            .component_for_kind(ComponentKind::DeclareObjectProperty)
-            .map(|x| x.clone())
-            .collect();
+        let dops: Vec<AnnotatedComponent<A>> =
+            self.closure_components_for_kind(ComponentKind::DeclareObjectProperty);
         for dop in dops {
-            match &dop.component {
-                Component::DeclareObjectProperty(DeclareObjectProperty(ObjectProperty(ii))) => {
-                    if ii.contains(&base.iri) {
-                        let op_display = self.build_entity_display(ii.clone());
-                        side_bar.object_props.push(op_display)
-                    }
-                }
-                _ => (),
+            if let Component::DeclareObjectProperty(DeclareObjectProperty(ObjectProperty(ii))) =
+                &dop.component
+                && ii.contains(&base.iri)
+            {
+                let op_display = self.build_entity_display(ii.clone());
+                side_bar.object_props.push(op_display)
             }
         }
-        let daps: Vec<AnnotatedComponent<A>> = self
-            .ontology
-            .component_for_kind(ComponentKind::DeclareAnnotationProperty)
-            .map(|x| x.clone())
-            .collect();
+        let daps: Vec<AnnotatedComponent<A>> =
+            self.closure_components_for_kind(ComponentKind::DeclareAnnotationProperty);
         for dap in daps {
-            match &dap.component {
-                Component::DeclareAnnotationProperty(DeclareAnnotationProperty(
-                    AnnotationProperty(ii),
-                )) => {
-                    if ii.contains(&base.iri) {
-                        let ap_display = self.build_entity_display(ii.clone());
-                        side_bar.annotation_props.push(ap_display)
-                    }
-                }
-                _ => (),
+            if let Component::DeclareAnnotationProperty(DeclareAnnotationProperty(
+                AnnotationProperty(ii),
+            )) = &dap.component
+                && ii.contains(&base.iri)
+            {
+                let ap_display = self.build_entity_display(ii.clone());
+                side_bar.annotation_props.push(ap_display)
             }
         }
-        let ddps: Vec<AnnotatedComponent<A>> = self
-            .ontology
-            .component_for_kind(ComponentKind::DeclareDataProperty)
-            .map(|x| x.clone())
-            .collect();
+        let ddps: Vec<AnnotatedComponent<A>> =
+            self.closure_components_for_kind(ComponentKind::DeclareDataProperty);
         for ddp in ddps {
-            match &ddp.component {
-                Component::DeclareDataProperty(dp) => {
-                    let class_iri = &dp.0.0;
-                    if class_iri.contains(&base.iri) {
-                        let iri_string = class_iri.to_string();
-                        let class_label =
-                            self.label_map.get(class_iri).unwrap_or(&iri_string).clone();
-                        let class_identifier = match self.prefix_mapping.shrink_iri(class_iri) {
-                            Ok(r) => r.to_string(),
-                            Err(_) => class_iri.to_string(),
-                        };
-                        side_bar.data_props.push(EntityDisplay::new(
-                            iri_string,
-                            class_identifier,
-                            class_label,
-                        ))
-                    }
+            if let Component::DeclareDataProperty(dp) = &ddp.component {
+                let class_iri = &dp.0.0;
+                if class_iri.contains(&base.iri) {
+                    let iri_string = class_iri.to_string();
+                    let class_label = self.label(class_iri).unwrap_or(&iri_string).clone();
+                    let class_identifier = match self.prefix_mapping.shrink_iri(class_iri) {
+                        Ok(r) => r.to_string(),
+                        Err(_) => class_iri.to_string(),
+                    };
+                    side_bar.data_props.push(EntityDisplay {
+                        url: self.entity_url(class_iri, &class_identifier),
+                        deprecated: self.deprecated.contains(class_iri),
+                        ..EntityDisplay::new(iri_string, class_identifier, class_label)
+                    })
                 }
-                _ => (),
             }
         }
+        let ddts: Vec<AnnotatedComponent<A>> =
+            self.closure_components_for_kind(ComponentKind::DeclareDatatype);
+        for ddt in ddts {
+            if let Component::DeclareDatatype(DeclareDatatype(Datatype(ii))) = &ddt.component
+                && ii.contains(&base.iri)
+            {
+                let dt_display = self.build_entity_display(ii.clone());
+                side_bar.datatypes.push(dt_display)
+            }
+        }
+        let exclude = self.settings.exclude_deprecated.unwrap_or(false);
+        for group in [
+            &mut side_bar.classes,
+            &mut side_bar.named_individuals,
+            &mut side_bar.object_props,
+            &mut side_bar.annotation_props,
+            &mut side_bar.data_props,
+            &mut side_bar.datatypes,
+        ] {
+            group.sort_by(|a, b| self.entity_order(a, b));
+        }
+        for group in [
+            &mut side_bar.classes,
+            &mut side_bar.named_individuals,
+            &mut side_bar.object_props,
+            &mut side_bar.annotation_props,
+            &mut side_bar.data_props,
+            &mut side_bar.datatypes,
+        ] {
+            let (obsolete, current) = std::mem::take(group)
+                .into_iter()
+                .partition(|e: &EntityDisplay| e.deprecated);
+            *group = current;
+            if !exclude {
+                side_bar.obsolete.extend(obsolete);
+            }
+        }
+        side_bar.obsolete.sort_by(|a, b| self.entity_order(a, b));
         Ok(side_bar)
     }
 
+    fn render_tree_json(&mut self, base: Option<&OntologyConfig>) -> Result<String> {
+        let b = match base {
+            Some(s) => s.clone(),
+            None => self.settings.ontology.clone(),
+        };
+        let side_bar = self.collect_entity_tree(&b)?;
+        serde_json::to_string_pretty(&side_bar.class_tree)
+            .wrap_err("Could not serialize class tree")
+    }
+
+    fn render_declaration_iri_export(
+        &mut self,
+        iri: &IRI<A>,
+        format: ExportFormat,
+    ) -> Result<Vec<u8>> {
+        let components = self.entity_components(iri);
+        write_components::<A, AA>(
+            components,
+            format,
+            &self.prefix_mapping,
+            Some(&self.settings.ontology.iri),
+        )
+        .wrap_err_with(|| format!("Could not export {iri} as {format:?}"))
+    }
+
+    fn render_ontology_export(&mut self, format: ExportFormat) -> Result<Vec<u8>> {
+        let mut components: Vec<AnnotatedComponent<A>> = self.ontology.iter().cloned().collect();
+        components.sort();
+        write_components::<A, AA>(
+            components,
+            format,
+            &self.prefix_mapping,
+            Some(&self.settings.ontology.iri),
+        )
+        .wrap_err_with(|| format!("Could not export the ontology as {format:?}"))
+    }
+
+    fn render_declaration_iri_json(&mut self, iri: &IRI<A>) -> Result<String> {
+        let (context, kind) = self.entity_context(iri);
+        if let Kind::Undefined = kind {
+            return Err(eyre::eyre!("{iri} is not declared"));
+        }
+        let serde_json::Value::Object(mut entity) = context.into_json() else {
+            return Err(eyre::eyre!("Could not serialize {iri}"));
+        };
+        for key in PAGE_ONLY_KEYS {
+            entity.remove(key);
+        }
+        entity.insert("schema_version".into(), JSON_SCHEMA_VERSION.into());
+        entity.insert("kind".into(), kind.name().into());
+        entity.insert("deprecated".into(), self.deprecated.contains(iri).into());
+        entity.insert("curie".into(), self.curie(iri).into());
+        entity.insert("parents".into(), self.parents(iri).into());
+        serde_json::to_string_pretty(&entity).wrap_err_with(|| format!("Could not serialize {iri}"))
+    }
+
+    fn render_entities_json(&mut self) -> Result<String> {
+        let mut entries: Vec<(EntityDisplay, CatalogueEntry)> = vec![];
+        for kind in DECLARATION_KINDS {
+            for iri in self.get_iris_for_declaration(kind) {
+                let display = self.build_entity_display(iri.clone());
+                let entry = CatalogueEntry {
+                    iri: iri.to_string(),
+                    curie: self.curie(&iri),
+                    label: self.label(&iri).cloned(),
+                    kind: Kind::of_declaration(kind).name(),
+                    parents: self.parents(&iri),
+                    deprecated: display.deprecated,
+                };
+                entries.push((display, entry));
+            }
+        }
+        entries.sort_by(|a, b| {
+            self.entity_order(&a.0, &b.0)
+                .then_with(|| a.1.kind.cmp(b.1.kind))
+        });
+        let catalogue = EntityCatalogue {
+            schema_version: JSON_SCHEMA_VERSION,
+            entities: entries.into_iter().map(|(_, e)| e).collect(),
+        };
+        serde_json::to_string_pretty(&catalogue).wrap_err("Could not serialize the entities")
+    }
+
+    fn render_search_json(&mut self) -> Result<String> {
+        Ok(self.search_index().to_json())
+    }
+
     fn unpack_class_expression(&self, ce: ClassExpression<A>) -> DisplayComp {
         match ce {
             ClassExpression::Class(class) => {
@@ -707,7 +738,13 @@ impl<A: ForIRI, AA: ForIndex<A>> IRIMappedRenderHTML<A> for OntologyRender<A, AA
                 let ce = self.unpack_class_expression(*class_expression);
                 DisplayComp::Not(Box::new(ce))
             }
-            ClassExpression::ObjectOneOf(_) => todo!("Not implemented: ObjectOneOf"),
+            ClassExpression::ObjectOneOf(individuals) => {
+                let v: Vec<DisplayComp> = individuals
+                    .into_iter()
+                    .map(|i| self.unpack_individual(i))
+                    .collect();
+                DisplayComp::OneOf(GroupDisplay(v))
+            }
             ClassExpression::ObjectSomeValuesFrom { ope, bce } => {
                 let ope = Box::new(self.unpack_object_property_expression(ope));
                 let ce = Box::new(self.unpack_class_expression(*bce));
@@ -718,67 +755,140 @@ impl<A: ForIRI, AA: ForIndex<A>> IRIMappedRenderHTML<A> for OntologyRender<A, AA
                 let ce = Box::new(self.unpack_class_expression(*bce));
                 DisplayComp::All(RelDisplay { rel: ope, ce })
             }
-            ClassExpression::ObjectHasValue {
-                ope,
-                i: Individual::Named(ind),
-            } => {
+            ClassExpression::ObjectHasValue { ope, i } => {
                 let op = self.unpack_object_property_expression(ope);
-                let ce = self.build_entity_display(ind.0);
+                let ce = self.unpack_individual(i);
                 DisplayComp::Value(RelDisplay {
                     rel: Box::new(op),
-                    ce: Box::new(DisplayComp::Simple(ce)),
+                    ce: Box::new(ce),
                 })
             }
-            ClassExpression::ObjectHasValue {
-                i: horned_owl::model::Individual::Anonymous(_),
-                ..
-            } => todo!(),
-            ClassExpression::ObjectHasSelf(_) => {
-                todo!("Not implemented: ObjectHasSelf")
-            }
-            ClassExpression::ObjectMinCardinality {
-                n: _,
-                ope: _,
-                bce: _,
-            } => {
-                todo!("Not implemented: ObjectMinCardinality")
-            }
-            ClassExpression::ObjectMaxCardinality {
-                n: _,
-                ope: _,
-                bce: _,
-            } => {
-                todo!("Not implemented: ObjectMaxCardinality")
-            }
-            ClassExpression::ObjectExactCardinality {
-                n: _,
-                ope: _,
-                bce: _,
-            } => {
-                todo!("Not implemented: ObjectExactCardinality")
-            }
-            ClassExpression::DataSomeValuesFrom { dp: _, dr: _ } => {
-                todo!("Not implemented: DataSomeValuesFrom")
-            }
-            ClassExpression::DataAllValuesFrom { dp: _, dr: _ } => {
-                todo!("Not implemented: DataAllValuesFrom")
+            ClassExpression::ObjectHasSelf(ope) => {
+                let op = self.unpack_object_property_expression(ope);
+                DisplayComp::Self_(Box::new(op))
+            }
+            ClassExpression::ObjectMinCardinality { n, ope, bce } => {
+                DisplayComp::Min(CardDisplay {
+                    n,
+                    rel: Box::new(self.unpack_object_property_expression(ope)),
+                    ce: Box::new(self.unpack_class_expression(*bce)),
+                })
+            }
+            ClassExpression::ObjectMaxCardinality { n, ope, bce } => {
+                DisplayComp::Max(CardDisplay {
+                    n,
+                    rel: Box::new(self.unpack_object_property_expression(ope)),
+                    ce: Box::new(self.unpack_class_expression(*bce)),
+                })
+            }
+            ClassExpression::ObjectExactCardinality { n, ope, bce } => {
+                DisplayComp::Exactly(CardDisplay {
+                    n,
+                    rel: Box::new(self.unpack_object_property_expression(ope)),
+                    ce: Box::new(self.unpack_class_expression(*bce)),
+                })
+            }
+            ClassExpression::DataSomeValuesFrom { dp, dr } => {
+                let dpd = self.build_entity_display(dp.0);
+                DisplayComp::DataSome(RelDisplay {
+                    rel: Box::new(DisplayComp::Simple(dpd)),
+                    ce: Box::new(DisplayComp::DataRange(self.unpack_data_range(dr))),
+                })
+            }
+            ClassExpression::DataAllValuesFrom { dp, dr } => {
+                let dpd = self.build_entity_display(dp.0);
+                DisplayComp::DataAll(RelDisplay {
+                    rel: Box::new(DisplayComp::Simple(dpd)),
+                    ce: Box::new(DisplayComp::DataRange(self.unpack_data_range(dr))),
+                })
             }
             ClassExpression::DataHasValue { dp, l } => {
                 let dpd = self.build_entity_display(dp.0);
-                let value = unpack_literal(l);
+                let value = self.build_literal_display(l);
                 DisplayComp::Data(DPDisplay {
                     dp: Box::new(DisplayComp::Simple(dpd)),
                     value,
                 })
             }
-            ClassExpression::DataMinCardinality { n: _, dp: _, dr: _ } => {
-                todo!("Not implemented: DataMinCardinality")
+            ClassExpression::DataMinCardinality { n, dp, dr } => DisplayComp::Min(CardDisplay {
+                n,
+                rel: Box::new(DisplayComp::Simple(self.build_entity_display(dp.0))),
+                ce: Box::new(DisplayComp::DataRange(self.unpack_data_range(dr))),
+            }),
+            ClassExpression::DataMaxCardinality { n, dp, dr } => DisplayComp::Max(CardDisplay {
+                n,
+                rel: Box::new(DisplayComp::Simple(self.build_entity_display(dp.0))),
+                ce: Box::new(DisplayComp::DataRange(self.unpack_data_range(dr))),
+            }),
+            ClassExpression::DataExactCardinality { n, dp, dr } => {
+                DisplayComp::Exactly(CardDisplay {
+                    n,
+                    rel: Box::new(DisplayComp::Simple(self.build_entity_display(dp.0))),
+                    ce: Box::new(DisplayComp::DataRange(self.unpack_data_range(dr))),
+                })
+            }
+        }
+    }
+
+    fn unpack_individual(&self, i: Individual<A>) -> DisplayComp {
+        match i {
+            Individual::Named(ind) => DisplayComp::Simple(self.build_entity_display(ind.0)),
+            Individual::Anonymous(anon) => DisplayComp::Anonymous(anon.0.to_string()),
+        }
+    }
+
+    fn build_literal_display(&self, l: Literal<A>) -> LiteralDisplay {
+        match l {
+            Literal::Simple { literal } => LiteralDisplay {
+                value: literal,
+                datatype: None,
+                lang: None,
+            },
+            Literal::Language { literal, lang } => LiteralDisplay {
+                value: literal,
+                datatype: None,
+                lang: Some(lang),
+            },
+            Literal::Datatype {
+                literal,
+                datatype_iri,
+            } => LiteralDisplay {
+                value: literal,
+                datatype: Some(self.build_entity_display(datatype_iri)),
+                lang: None,
+            },
+        }
+    }
+
+    fn unpack_data_range(&self, dr: DataRange<A>) -> DataRangeDisplay {
+        match dr {
+            DataRange::Datatype(dt) => DataRangeDisplay::Datatype(self.build_entity_display(dt.0)),
+            DataRange::DataIntersectionOf(drs) => {
+                DataRangeDisplay::And(drs.into_iter().map(|d| self.unpack_data_range(d)).collect())
+            }
+            DataRange::DataUnionOf(drs) => {
+                DataRangeDisplay::Or(drs.into_iter().map(|d| self.unpack_data_range(d)).collect())
             }
-            ClassExpression::DataMaxCardinality { n: _, dp: _, dr: _ } => {
-                todo!("Not implemented: DataMaxCardinality")
+            DataRange::DataComplementOf(d) => {
+                DataRangeDisplay::Not(Box::new(self.unpack_data_range(*d)))
             }
-            ClassExpression::DataExactCardinality { n: _, dp: _, dr: _ } => {
-                todo!("Not implemented: DataExactCardinality")
+            DataRange::DataOneOf(ls) => DataRangeDisplay::OneOf(
+                ls.into_iter()
+                    .map(|l| self.build_literal_display(l))
+                    .collect(),
+            ),
+            DataRange::DatatypeRestriction(dt, frs) => {
+                let facets = frs
+                    .into_iter()
+                    .map(|fr| FacetDisplay {
+                        facet: facet_symbol(&fr.f).to_string(),
+                        value: unpack_literal(fr.l),
+                    })
+                    .collect();
+                DataRangeDisplay::Restriction(RestrictionDisplay {
+                    datatype: self.build_entity_display(dt.0),
+                    facets,
+                })
             }
         }
     }
@@ -802,14 +912,18 @@ impl<A: ForIRI, AA: ForIndex<A>> IRIMappedRenderHTML<A> for OntologyRender<A, AA
         } else {
             iri.to_string()
         };
-        let entity_label = match self.label_map.get(&iri) {
+        let entity_label = match self.label(&iri) {
             Some(l) => l.clone(),
             None => match self.prefix_mapping.shrink_iri(iri.as_ref()) {
                 Ok(r) => r.to_string(),
                 Err(_) => iri.to_string(),
             },
         };
-        EntityDisplay::new(iri.to_string(), entity_id, entity_label)
+        EntityDisplay {
+            url: self.entity_url(&iri, &entity_id),
+            deprecated: self.deprecated.contains(&iri),
+            ..EntityDisplay::new(iri.to_string(), entity_id, entity_label)
+        }
     }
 
     fn unpack_object_property_expression(&self, ope: ObjectPropertyExpression<A>) -> DisplayComp {
@@ -820,75 +934,1284 @@ impl<A: ForIRI, AA: ForIndex<A>> IRIMappedRenderHTML<A> for OntologyRender<A, AA
             }
             ObjectPropertyExpression::InverseObjectProperty(object_property) => {
                 let op_display = self.build_entity_display(object_property.0.clone());
-                DisplayComp::Simple(op_display)
+                DisplayComp::Inverse(Box::new(DisplayComp::Simple(op_display)))
             }
         }
     }
 }
 
 impl<A: ForIRI, AA: ForIndex<A>> OntologyRender<A, AA> {
-    pub fn new_with_settings(settings: Settings) -> Result<Self> {
-        let dir = if let Some(d) = &settings.ontology.source {
-            d
-        } else {
-            return Err(eyre::eyre!("Expected source file"));
-        };
-        let build: Build<A> = Build::new();
-        let f = File::open(dir)?;
-        let reader = BufReader::new(f);
-        let r = read_with_build(reader, &build);
-        assert!(r.is_ok(), "Expected ontology, got failure:{:?}", r.err());
-        let (o, mut prefix_mapping) = r.ok().unwrap();
-        let mut ontology: IRIMappedOntology<A, AA> = IRIMappedOntology::from(o);
-        let label_map = get_label_hashmap(&mut ontology);
-        prefix_mapping.set_default(&settings.ontology.iri);
-        if let Some(imports) = &settings.import {
-            for imp in imports.iter() {
-                if let Some(p) = &imp.suffix {
-                    let adapted = format!("{p}");
-                    let exist: Vec<String> = prefix_mapping
-                        .mappings()
-                        .filter(|(_, v)| *v == &imp.iri)
-                        .map(|(p, _)| p.clone())
-                        .collect();
-                    if exist.len() > 0 {
-                        for e in exist.iter() {
-                            prefix_mapping.remove_prefix(e);
+    /// Renders the page of an entity with `template`, either the full page
+    /// or the fragment embedded in single page builds.
+    fn render_entity(&mut self, iri: &IRI<A>, template: &str) -> Result<String> {
+        let (context, this_kind) = self.entity_context(iri);
+        match this_kind {
+            Kind::Class => self
+                .templates
+                .render(template, &context)
+                .wrap_err("Could not render class page"),
+            Kind::ObjectProperty => self
+                .templates
+                .render(template, &context)
+                .wrap_err("Could not render object property page"),
+            Kind::AnnotationProperty => self
+                .templates
+                .render(template, &context)
+                .wrap_err("Could not render annotation property page"),
+            Kind::NamedIndividual => self
+                .templates
+                .render(template, &context)
+                .wrap_err("Could not render named individual page"),
+            Kind::DataProperty => self
+                .templates
+                .render(template, &context)
+                .wrap_err("Could not render data property page"),
+            Kind::Datatype => self
+                .templates
+                .render(template, &context)
+                .wrap_err("Could not render datatype page"),
+            Kind::Undefined => {
+                Err(tera::Error::msg("Not implemented")).wrap_err("Unkown entity kind")
+            }
+        }
+    }
+
+    /// Context of the page of an entity, with its kind.
+    fn entity_context(&mut self, iri: &IRI<A>) -> (TeraContext, Kind) {
+        let mut context = TeraContext::new();
+        let mut annotations: Vec<OntologyAnnotation> = vec![];
+        let mut this_kind: Kind = Kind::Undefined;
+        let mut super_entities: Vec<ExpressionDisplay<DisplayComp>> = vec![];
+        let mut inverse_ops: Vec<ExpressionDisplay<DisplayComp>> = vec![];
+        let mut sub_entities: Vec<ExpressionDisplay<DisplayComp>> = vec![];
+        let mut equivalent_classes: Vec<ExpressionDisplay<DisplayComp>> = vec![];
+        let mut class_assertions: Vec<ExpressionDisplay<DisplayComp>> = vec![];
+        let mut facts: Vec<FactDisplay> = vec![];
+        let mut incoming_facts: Vec<FactDisplay> = vec![];
+        let mut same_individuals: Vec<ExpressionDisplay<DisplayComp>> = vec![];
+        let mut different_individuals: Vec<ExpressionDisplay<DisplayComp>> = vec![];
+        let mut datatype_definitions: Vec<ExpressionDisplay<DataRangeDisplay>> = vec![];
+        let mut characteristics: Vec<Characteristic> = vec![];
+        let mut property_chains: Vec<ExpressionDisplay<DisplayComp>> = vec![];
+        let mut equivalent_properties: Vec<ExpressionDisplay<DisplayComp>> = vec![];
+        let mut disjoint_with: Vec<ExpressionDisplay<DisplayComp>> = vec![];
+        let mut disjoint_unions: Vec<MembersDisplay> = vec![];
+        let mut has_keys: Vec<MembersDisplay> = vec![];
+        let roles = self.settings.annotations.clone().unwrap_or_default();
+        let mut definitions: HashMap<Option<String>, (usize, OntologyAnnotation)> = HashMap::new();
+        let mut examples: HashMap<Option<String>, (usize, OntologyAnnotation)> = HashMap::new();
+        let mut synonyms: Vec<OntologyAnnotation> = vec![];
+        let mut comments: Vec<OntologyAnnotation> = vec![];
+        let mut editor_notes: Vec<OntologyAnnotation> = vec![];
+        let mut replaced_by: Vec<EntityDisplay> = vec![];
+        let mut consider: Vec<EntityDisplay> = vec![];
+        context.insert("base", &self.base());
+        let anns: Vec<AnnotatedComponent<A>> = self.closure_components_for_iri(iri);
+        for ann_cmp in anns {
+            let cmp = &ann_cmp.component;
+            match cmp {
+                Component::DeclareClass(dc) => {
+                    context.insert("iri", dc.0.0.as_ref());
+                    this_kind = Kind::Class;
+                    context.insert("kind", "klss")
+                }
+                Component::DeclareObjectProperty(op) => {
+                    context.insert("iri", op.0.0.as_ref());
+                    this_kind = Kind::ObjectProperty;
+                    context.insert("kind", "object-property")
+                }
+                Component::DeclareAnnotationProperty(ap) => {
+                    context.insert("iri", ap.0.0.as_ref());
+                    this_kind = Kind::AnnotationProperty;
+                    context.insert("kind", "annotation-property")
+                }
+                Component::DeclareNamedIndividual(ni) => {
+                    context.insert("iri", ni.0.0.as_ref());
+                    this_kind = Kind::NamedIndividual;
+                    context.insert("kind", "named-individual")
+                }
+                Component::DeclareDataProperty(dp) => {
+                    context.insert("iri", dp.0.0.as_ref());
+                    this_kind = Kind::DataProperty;
+                    context.insert("kind", "data-property")
+                }
+                Component::DeclareDatatype(dt) => {
+                    context.insert("iri", dt.0.0.as_ref());
+                    this_kind = Kind::Datatype;
+                    context.insert("kind", "datatype")
+                }
+                Component::AnnotationAssertion(aa) if matches!(&aa.subject, AnnotationSubject::IRI(s) if s == iri) =>
+                {
+                    let Some(mut annotation) = self.build_annotation_display(&aa.ann) else {
+                        continue;
+                    };
+                    annotation.annotations = self.build_annotation_displays(&ann_cmp.ann);
+                    match roles.role(aa.ann.ap.0.as_ref()) {
+                        Some((AnnotationRole::Label, _)) => (),
+                        Some((AnnotationRole::Definition, rank)) => {
+                            keep_preferred(&mut definitions, rank, annotation)
+                        }
+                        Some((AnnotationRole::Example, rank)) => {
+                            keep_preferred(&mut examples, rank, annotation)
+                        }
+                        Some((AnnotationRole::Synonym, _)) => synonyms.push(annotation),
+                        Some((AnnotationRole::Comment, _)) => comments.push(annotation),
+                        Some((AnnotationRole::EditorNote, _)) => editor_notes.push(annotation),
+                        Some((AnnotationRole::Deprecated, _)) => (),
+                        Some((AnnotationRole::ReplacedBy, _)) => {
+                            replaced_by.push(self.replacement_display(&aa.ann.av))
                         }
+                        Some((AnnotationRole::Consider, _)) => {
+                            consider.push(self.replacement_display(&aa.ann.av))
+                        }
+                        None => annotations.push(annotation),
                     }
-                    match prefix_mapping.add_prefix(&adapted, &imp.iri) {
-                        Ok(_) => (),
-                        Err(_) => return Err(eyre::eyre!("Error when adding prefixes")),
-                    };
                 }
-            }
-        }
-        let templates: Tera = {
-            let templates_dir = match &settings.templates {
-                Some(dir) => {
-                    let mut out = String::from(dir.trim_end_matches("/"));
-                    out.push_str("/**/*.html");
-                    out
+                Component::SubClassOf(SubClassOf {
+                    sup: ClassExpression::Class(spc),
+                    sub: ClassExpression::Class(subc),
+                }) => {
+                    if &spc.0 == iri {
+                        let child_display = self.build_entity_display(subc.0.clone());
+                        sub_entities
+                            .push(self.annotated(DisplayComp::Simple(child_display), &ann_cmp.ann))
+                    } else if &subc.0 == iri {
+                        let parent_display = self.build_entity_display(spc.0.clone());
+                        super_entities.push(
+                            self.annotated(DisplayComp::Simple(parent_display), &ann_cmp.ann),
+                        );
+                    }
                 }
-                None => {
-                    return Err(eyre::eyre!(
-                        "Templates not defined, set templates directory"
-                    ));
+                Component::SubClassOf(SubClassOf {
+                    sup,
+                    sub: ClassExpression::Class(subc),
+                }) if &subc.0 == iri => {
+                    let class_display = self.unpack_class_expression(sup.clone());
+                    super_entities.push(self.annotated(class_display, &ann_cmp.ann));
                 }
-            };
-            let mut tera = match Tera::new(&templates_dir) {
-                Ok(t) => t,
-                Err(e) => return Err(eyre::eyre!("Parsing error(s): {}", e)),
-            };
-            tera.autoescape_on(vec![".html", ".sql"]);
-            tera
-        };
-        Ok(OntologyRender {
-            ontology,
-            prefix_mapping,
+                Component::SubClassOf(SubClassOf {
+                    sup: ClassExpression::Class(supc),
+                    sub,
+                }) if &supc.0 == iri => {
+                    let class_display = self.unpack_class_expression(sub.clone());
+                    sub_entities.push(self.annotated(class_display, &ann_cmp.ann));
+                }
+                Component::SubObjectPropertyOf(SubObjectPropertyOf {
+                    sup,
+                    sub: SubObjectPropertyExpression::ObjectPropertyExpression(sub),
+                }) => {
+                    if names_property(sup, iri) {
+                        let child_display = self.unpack_object_property_expression(sub.clone());
+                        sub_entities.push(self.annotated(child_display, &ann_cmp.ann))
+                    } else if names_property(sub, iri) {
+                        let parent_display = self.unpack_object_property_expression(sup.clone());
+                        super_entities.push(self.annotated(parent_display, &ann_cmp.ann));
+                    }
+                }
+                Component::SubObjectPropertyOf(SubObjectPropertyOf {
+                    sup: ObjectPropertyExpression::ObjectProperty(sup),
+                    sub: SubObjectPropertyExpression::ObjectPropertyChain(chain),
+                }) if &sup.0 == iri => {
+                    let links: Vec<DisplayComp> = chain
+                        .iter()
+                        .map(|ope| self.unpack_object_property_expression(ope.clone()))
+                        .collect();
+                    property_chains.push(
+                        self.annotated(DisplayComp::Chain(GroupDisplay(links)), &ann_cmp.ann),
+                    );
+                }
+                Component::SubDataPropertyOf(SubDataPropertyOf { sup, sub }) => {
+                    if &sup.0 == iri {
+                        let child_display = self.build_entity_display(sub.0.clone());
+                        sub_entities
+                            .push(self.annotated(DisplayComp::Simple(child_display), &ann_cmp.ann))
+                    } else if &sub.0 == iri {
+                        let parent_display = self.build_entity_display(sup.0.clone());
+                        super_entities.push(
+                            self.annotated(DisplayComp::Simple(parent_display), &ann_cmp.ann),
+                        );
+                    }
+                }
+                Component::SubAnnotationPropertyOf(SubAnnotationPropertyOf { sup, sub }) => {
+                    if &sup.0 == iri {
+                        let child_display = self.build_entity_display(sub.0.clone());
+                        sub_entities
+                            .push(self.annotated(DisplayComp::Simple(child_display), &ann_cmp.ann))
+                    } else if &sub.0 == iri {
+                        let parent_display = self.build_entity_display(sup.0.clone());
+                        super_entities.push(
+                            self.annotated(DisplayComp::Simple(parent_display), &ann_cmp.ann),
+                        );
+                    }
+                }
+                Component::EquivalentClasses(EquivalentClasses(ecs))
+                    if ecs.iter().any(|ce| names_class(ce, iri)) =>
+                {
+                    let ecx: Vec<DisplayComp> = ecs
+                        .iter()
+                        .map(|e| self.unpack_class_expression(e.clone()))
+                        .filter(|ex| {
+                            if let DisplayComp::Simple(e) = ex {
+                                e.iri != iri.as_ref()
+                            } else {
+                                true
+                            }
+                        })
+                        .collect();
+                    equivalent_classes
+                        .extend(ecx.into_iter().map(|e| self.annotated(e, &ann_cmp.ann)))
+                }
+                Component::EquivalentObjectProperties(EquivalentObjectProperties(opes))
+                    if opes.iter().any(|ope| names_property(ope, iri)) =>
+                {
+                    let epx = opes
+                        .iter()
+                        .filter(|ope| !names_property(ope, iri))
+                        .map(|ope| self.unpack_object_property_expression(ope.clone()));
+                    equivalent_properties.extend(epx.map(|e| self.annotated(e, &ann_cmp.ann)))
+                }
+                Component::EquivalentDataProperties(EquivalentDataProperties(dps))
+                    if dps.iter().any(|dp| &dp.0 == iri) =>
+                {
+                    let epx = dps
+                        .iter()
+                        .filter(|dp| &dp.0 != iri)
+                        .map(|dp| DisplayComp::Simple(self.build_entity_display(dp.0.clone())));
+                    equivalent_properties.extend(epx.map(|e| self.annotated(e, &ann_cmp.ann)))
+                }
+                Component::InverseObjectProperties(InverseObjectProperties(iop, iiop)) => {
+                    if &iop.0 == iri {
+                        let op_display = self.build_entity_display(iiop.0.clone());
+                        inverse_ops
+                            .push(self.annotated(DisplayComp::Simple(op_display), &ann_cmp.ann));
+                    } else if &iiop.0 == iri {
+                        let op_display = self.build_entity_display(iop.0.clone());
+                        inverse_ops
+                            .push(self.annotated(DisplayComp::Simple(op_display), &ann_cmp.ann));
+                    }
+                }
+                Component::ObjectPropertyRange(ObjectPropertyRange {
+                    ope: ObjectPropertyExpression::ObjectProperty(ObjectProperty(ii)),
+                    ce,
+                }) if ii == iri => {
+                    let ce_display = self.unpack_class_expression(ce.clone());
+                    context.insert("op_range", &self.annotated(ce_display, &ann_cmp.ann));
+                }
+                Component::ObjectPropertyDomain(ObjectPropertyDomain {
+                    ope: ObjectPropertyExpression::ObjectProperty(ObjectProperty(ii)),
+                    ce,
+                }) if ii == iri => {
+                    let ce_display = self.unpack_class_expression(ce.clone());
+                    context.insert("op_domain", &self.annotated(ce_display, &ann_cmp.ann));
+                }
+                Component::DataPropertyRange(DataPropertyRange {
+                    dp: DataProperty(ii),
+                    dr,
+                }) if ii == iri => {
+                    let dr_display = self.unpack_data_range(dr.clone());
+                    context.insert("dp_range", &self.annotated(dr_display, &ann_cmp.ann));
+                }
+                Component::DatatypeDefinition(DatatypeDefinition { kind, range })
+                    if &kind.0 == iri =>
+                {
+                    let dr_display = self.unpack_data_range(range.clone());
+                    datatype_definitions.push(self.annotated(dr_display, &ann_cmp.ann));
+                }
+                Component::DataPropertyDomain(DataPropertyDomain {
+                    dp: DataProperty(ii),
+                    ce,
+                }) if ii == iri => {
+                    let ce_display = self.unpack_class_expression(ce.clone());
+                    context.insert("op_domain", &self.annotated(ce_display, &ann_cmp.ann));
+                }
+                Component::DisjointClasses(DisjointClasses(ces))
+                    if ces.iter().any(|ce| names_class(ce, iri)) =>
+                {
+                    let dcx = ces
+                        .iter()
+                        .filter(|ce| !names_class(ce, iri))
+                        .map(|ce| self.unpack_class_expression(ce.clone()));
+                    disjoint_with.extend(dcx.map(|e| self.annotated(e, &ann_cmp.ann)))
+                }
+                Component::DisjointObjectProperties(DisjointObjectProperties(opes))
+                    if opes.iter().any(|ope| names_property(ope, iri)) =>
+                {
+                    let dpx = opes
+                        .iter()
+                        .filter(|ope| !names_property(ope, iri))
+                        .map(|ope| self.unpack_object_property_expression(ope.clone()));
+                    disjoint_with.extend(dpx.map(|e| self.annotated(e, &ann_cmp.ann)))
+                }
+                Component::DisjointDataProperties(DisjointDataProperties(dps))
+                    if dps.iter().any(|dp| &dp.0 == iri) =>
+                {
+                    let dpx = dps
+                        .iter()
+                        .filter(|dp| &dp.0 != iri)
+                        .map(|dp| DisplayComp::Simple(self.build_entity_display(dp.0.clone())));
+                    disjoint_with.extend(dpx.map(|e| self.annotated(e, &ann_cmp.ann)))
+                }
+                Component::DisjointUnion(DisjointUnion(cls, ces)) if &cls.0 == iri => {
+                    let members: Vec<DisplayComp> = ces
+                        .iter()
+                        .map(|ce| self.unpack_class_expression(ce.clone()))
+                        .collect();
+                    disjoint_unions.push(MembersDisplay {
+                        members: self.expressions(members),
+                        annotations: self.build_annotation_displays(&ann_cmp.ann),
+                    });
+                }
+                Component::HasKey(HasKey { ce, vpe }) if names_class(ce, iri) => {
+                    let keys: Vec<DisplayComp> = vpe
+                        .iter()
+                        .map(|pe| match pe {
+                            PropertyExpression::ObjectPropertyExpression(ope) => {
+                                self.unpack_object_property_expression(ope.clone())
+                            }
+                            PropertyExpression::DataProperty(dp) => {
+                                DisplayComp::Simple(self.build_entity_display(dp.0.clone()))
+                            }
+                            PropertyExpression::AnnotationProperty(ap) => {
+                                DisplayComp::Simple(self.build_entity_display(ap.0.clone()))
+                            }
+                        })
+                        .collect();
+                    has_keys.push(MembersDisplay {
+                        members: self.expressions(keys),
+                        annotations: self.build_annotation_displays(&ann_cmp.ann),
+                    });
+                }
+                Component::AnnotationPropertyRange(_) => (),
+                Component::AnnotationPropertyDomain(_) => (),
+                Component::ClassAssertion(ClassAssertion {
+                    ce,
+                    i: Individual::Named(ind),
+                }) if &ind.0 == iri => {
+                    let cexp = self.unpack_class_expression(ce.clone());
+                    class_assertions.push(self.annotated(cexp, &ann_cmp.ann));
+                }
+                Component::ObjectPropertyAssertion(ObjectPropertyAssertion { ope, from, to })
+                | Component::NegativeObjectPropertyAssertion(NegativeObjectPropertyAssertion {
+                    ope,
+                    from,
+                    to,
+                }) => {
+                    let negative = matches!(cmp, Component::NegativeObjectPropertyAssertion(_));
+                    if names_individual(from, iri) {
+                        facts.push(FactDisplay {
+                            property: self
+                                .expression(self.unpack_object_property_expression(ope.clone())),
+                            value: self.expression(self.unpack_individual(to.clone())),
+                            negative,
+                            annotations: self.build_annotation_displays(&ann_cmp.ann),
+                        });
+                    } else if names_individual(to, iri) {
+                        incoming_facts.push(FactDisplay {
+                            property: self
+                                .expression(self.unpack_object_property_expression(ope.clone())),
+                            value: self.expression(self.unpack_individual(from.clone())),
+                            negative,
+                            annotations: self.build_annotation_displays(&ann_cmp.ann),
+                        });
+                    }
+                }
+                Component::DataPropertyAssertion(DataPropertyAssertion { dp, from, to })
+                | Component::NegativeDataPropertyAssertion(NegativeDataPropertyAssertion {
+                    dp,
+                    from,
+                    to,
+                }) if names_individual(from, iri) => {
+                    let dp_display = self.build_entity_display(dp.0.clone());
+                    let literal = self.build_literal_display(to.clone());
+                    facts.push(FactDisplay {
+                        property: self.expression(DisplayComp::Simple(dp_display)),
+                        value: self.expression(DisplayComp::Literal(literal)),
+                        negative: matches!(cmp, Component::NegativeDataPropertyAssertion(_)),
+                        annotations: self.build_annotation_displays(&ann_cmp.ann),
+                    });
+                }
+                Component::SameIndividual(SameIndividual(inds))
+                    if inds.iter().any(|i| names_individual(i, iri)) =>
+                {
+                    let six = inds
+                        .iter()
+                        .filter(|i| !names_individual(i, iri))
+                        .map(|i| self.unpack_individual(i.clone()));
+                    same_individuals.extend(six.map(|e| self.annotated(e, &ann_cmp.ann)))
+                }
+                Component::DifferentIndividuals(DifferentIndividuals(inds))
+                    if inds.iter().any(|i| names_individual(i, iri)) =>
+                {
+                    let dix = inds
+                        .iter()
+                        .filter(|i| !names_individual(i, iri))
+                        .map(|i| self.unpack_individual(i.clone()));
+                    different_individuals.extend(dix.map(|e| self.annotated(e, &ann_cmp.ann)))
+                }
+                _ => {
+                    if let Some(c) = characteristic(cmp, iri) {
+                        characteristics.push(c);
+                    }
+                }
+            }
+        }
+        characteristics.sort();
+        characteristics.dedup();
+        let preferred = self.preferred_languages();
+        if let Some(label) = self.label(iri) {
+            context.insert("label", label);
+        }
+        if let Some(lang) = best_language(definitions.keys(), &preferred) {
+            let (_, definition) = &definitions[lang];
+            context.insert("definition", &definition.value);
+            context.insert("definition_annotations", &definition.annotations);
+        }
+        if let Some(lang) = best_language(examples.keys(), &preferred) {
+            let (_, example) = &examples[lang];
+            context.insert("example", &example.value);
+            context.insert("example_annotations", &example.annotations);
+        }
+        let synonyms = self.in_preferred_language(synonyms);
+        if !synonyms.is_empty() {
+            context.insert("synonyms", &synonyms);
+        }
+        let comments = self.in_preferred_language(comments);
+        if !comments.is_empty() {
+            context.insert("comments", &comments);
+        }
+        if !editor_notes.is_empty() {
+            context.insert("editor_notes", &editor_notes);
+        }
+        if self.deprecated.contains(iri) {
+            context.insert("deprecated", &true);
+        }
+        if !replaced_by.is_empty() {
+            context.insert("replaced_by", &replaced_by);
+        }
+        if !consider.is_empty() {
+            context.insert("consider", &consider);
+        }
+        let annotations = self.in_preferred_language(annotations);
+        let identifier = self.build_entity_display(iri.clone()).identifier;
+        self.insert_language_context(&mut context, &format!("{identifier}.html"));
+        if !self.single_page() {
+            let stem = format!("{}{}", self.base(), identifier.replace(':', "/"));
+            context.insert("exports", &export_links(&stem));
+        }
+        let usages: Vec<UsageDisplay> = self
+            .usage
+            .get(iri)
+            .iter()
+            .filter_map(|c| self.usage_display(c))
+            .collect();
+        if !usages.is_empty() {
+            context.insert("usages", &usages);
+        }
+        if !characteristics.is_empty() {
+            context.insert("characteristics", &characteristics);
+        }
+        if !equivalent_properties.is_empty() {
+            context.insert("equivalent_properties", &equivalent_properties);
+        }
+        if !disjoint_with.is_empty() {
+            context.insert("disjoint_with", &disjoint_with);
+        }
+        if !disjoint_unions.is_empty() {
+            context.insert("disjoint_unions", &disjoint_unions);
+        }
+        if !has_keys.is_empty() {
+            context.insert("has_keys", &has_keys);
+        }
+        if !property_chains.is_empty() {
+            context.insert("property_chains", &property_chains);
+        }
+        if !super_entities.is_empty() {
+            context.insert("super_classes", &super_entities);
+        }
+        if !sub_entities.is_empty() {
+            context.insert("sub_classes", &sub_entities);
+        }
+        if !inverse_ops.is_empty() {
+            context.insert("inverse_ops", &inverse_ops);
+        }
+        if !equivalent_classes.is_empty() {
+            context.insert("equivalent_classes", &equivalent_classes);
+        }
+        if !class_assertions.is_empty() {
+            context.insert("class_assertions", &class_assertions);
+        }
+        if !facts.is_empty() {
+            context.insert("facts", &facts);
+        }
+        if !incoming_facts.is_empty() {
+            context.insert("incoming_facts", &incoming_facts);
+        }
+        if !same_individuals.is_empty() {
+            context.insert("same_individuals", &same_individuals);
+        }
+        if !different_individuals.is_empty() {
+            context.insert("different_individuals", &different_individuals);
+        }
+        if !datatype_definitions.is_empty() {
+            context.insert("datatype_definitions", &datatype_definitions);
+        }
+        context.insert("annotations", &annotations);
+        let s = if let Some(f) = &self.settings.ontology.suffix {
+            f
+        } else {
+            &self.settings.ontology.iri
+        };
+        context.insert("title", &s);
+        (context, this_kind)
+    }
+
+    /// Context of the ontology index page.
+    fn metadata_context(&mut self, base: Option<&OntologyConfig>) -> Result<TeraContext> {
+        let b = match base {
+            Some(s) => s,
+            None => &self.settings.ontology.clone(),
+        };
+        let mut context = TeraContext::default();
+        context.insert("base", &self.base());
+        if b.iri == self.settings.ontology.iri {
+            let mut contributors: Vec<OntologyAnnotation> = vec![];
+            let mut annotations: Vec<OntologyAnnotation> = vec![];
+            for oid in self.ontology.component_for_kind(ComponentKind::OntologyID) {
+                if let Component::OntologyID(oi) = &oid.component {
+                    if let Some(i) = &oi.viri {
+                        context.insert("version", i.as_ref())
+                    }
+                    if let Some(i) = &oi.iri {
+                        context.insert("iri", i.as_ref())
+                    }
+                }
+            }
+            let oanns: Vec<AnnotatedComponent<A>> = self
+                .ontology
+                .component_for_kind(ComponentKind::OntologyAnnotation)
+                .cloned()
+                .collect();
+            let oas = self.in_preferred_language(
+                oanns
+                    .iter()
+                    .filter_map(|oann| match &oann.component {
+                        Component::OntologyAnnotation(oa) => self.build_annotation_display(&oa.0),
+                        _ => None,
+                    })
+                    .collect(),
+            );
+            for aa in oas {
+                match aa.iri.as_str() {
+                    "http://purl.org/dc/elements/1.1/contributor" => contributors.push(aa),
+                    "http://purl.org/dc/terms/title" => context.insert("title", &aa.value),
+                    "http://purl.org/dc/elements/1.1/license" => {
+                        context.insert("license", &aa.value)
+                    }
+                    "http://purl.org/dc/terms/license" => context.insert("license", &aa.value),
+                    "http://purl.org/dc/terms/description" => {
+                        context.insert("description", &aa.value)
+                    }
+                    _ => annotations.push(aa),
+                }
+            }
+            context.insert("annotations", &annotations);
+            context.insert("contributors", &contributors);
+        }
+
+        let mut ontology_index: Vec<EntityDisplay> = vec![];
+        let s = if let Some(f) = &self.settings.title {
+            f
+        } else {
+            "Ontology Viewer"
+        };
+        context.insert("title", s);
+        ontology_index.push(EntityDisplay {
+            iri: self.settings.ontology.iri.to_string(),
+            identifier: String::new(),
+            display: if let Some(f) = &self.settings.ontology.suffix {
+                f.clone()
+            } else {
+                String::from("Base")
+            },
+            url: self.base(),
+            deprecated: false,
+        });
+        if let Some(v) = &self.settings.import {
+            for vc in v.iter() {
+                if let Some(e) = &vc.suffix {
+                    ontology_index.push(EntityDisplay {
+                        iri: vc.iri.clone(),
+                        display: e.clone(),
+                        identifier: e.clone(),
+                        url: format!("{}{e}", self.base()),
+                        deprecated: false,
+                    })
+                }
+            }
+        }
+        context.insert("ontology_index", &ontology_index);
+        let entity_tree = match self.collect_entity_tree(b) {
+            Ok(sb) => sb,
+            Err(e) => {
+                return Err(eyre::Report::msg(format!(
+                    "Failed to collect entities for sidebar, context: {}",
+                    e
+                )));
+            }
+        };
+        context.insert("sidebar", &entity_tree);
+        let page = match base {
+            Some(OntologyConfig {
+                suffix: Some(suffix),
+                ..
+            }) => format!("{suffix}/"),
+            _ => String::new(),
+        };
+        self.insert_language_context(&mut context, &page);
+        if base.is_none() {
+            let stem = format!("{}ontology", self.base());
+            context.insert("exports", &export_links(&stem));
+        }
+        Ok(context)
+    }
+
+    /// Configured languages, starting with the one of the site being built.
+    fn preferred_languages(&self) -> Vec<String> {
+        let configured = self.settings.languages.iter().flatten();
+        let preferred: Vec<String> = self
+            .language
+            .iter()
+            .chain(configured.filter(|l| Some(*l) != self.language.as_ref()))
+            .cloned()
+            .collect();
+        if preferred.is_empty() {
+            // Pages are in English unless told otherwise, see `base.html`.
+            vec![DEFAULT_LANGUAGE.to_string()]
+        } else {
+            preferred
+        }
+    }
+
+    /// Base URL of the site being built, under `/<lang>/` for language sites.
+    fn base(&self) -> String {
+        let root = self.settings.baseurl.as_deref().unwrap_or("/");
+        match &self.language {
+            Some(lang) => format!("{root}{lang}/"),
+            None => root.to_string(),
+        }
+    }
+
+    fn single_page(&self) -> bool {
+        self.settings.build.as_ref().is_some_and(|b| b.single_page)
+    }
+
+    /// Link to the page of an entity, or to its section in single page builds.
+    /// There, entities without a section, e.g. `xsd:string`, link to their IRI.
+    fn entity_url(&self, iri: &IRI<A>, identifier: &str) -> String {
+        if !self.single_page() {
+            format!("{}{identifier}.html", self.base())
+        } else if self.declared.contains(iri) {
+            format!("#{iri}")
+        } else {
+            iri.to_string()
+        }
+    }
+
+    /// Label of `iri` in the most preferred language available.
+    fn label(&self, iri: &IRI<A>) -> Option<&String> {
+        let labels = self.label_map.get(iri)?;
+        let lang = best_language(labels.keys(), &self.preferred_languages())?;
+        labels.get(lang)
+    }
+
+    /// `iri` shortened with the prefixes of the ontology, or in full when no
+    /// prefix matches.
+    fn curie(&self, iri: &IRI<A>) -> String {
+        match self.prefix_mapping.shrink_iri(iri.as_ref()) {
+            // The ontology IRI is the default prefix, written `:` as in Turtle.
+            Ok(c) if iri.starts_with(&self.settings.ontology.iri) => format!(":{c}"),
+            Ok(c) => c.to_string(),
+            Err(_) => iri.to_string(),
+        }
+    }
+
+    /// Asserted subclass hierarchy of `classes`. Roots are the classes with
+    /// no named superclass among them. A class with several parents is shown
+    /// under each, and cycles are cut where they would repeat a class.
+    fn class_tree(&mut self, classes: &[IRI<A>]) -> Vec<TreeNode> {
+        let members: HashSet<&IRI<A>> = classes.iter().collect();
+        let mut children: HashMap<IRI<A>, HashSet<IRI<A>>> = HashMap::new();
+        let mut has_parent: HashSet<IRI<A>> = HashSet::new();
+        for sco in self.closure_components_for_kind(ComponentKind::SubClassOf) {
+            if let Component::SubClassOf(SubClassOf {
+                sub: ClassExpression::Class(sub),
+                sup: ClassExpression::Class(sup),
+            }) = &sco.component
+                && sub != sup
+                && members.contains(&sub.0)
+                && members.contains(&sup.0)
+            {
+                children
+                    .entry(sup.0.clone())
+                    .or_default()
+                    .insert(sub.0.clone());
+                has_parent.insert(sub.0.clone());
+            }
+        }
+        let mut reached: HashSet<IRI<A>> = HashSet::new();
+        let mut roots: Vec<TreeNode> = vec![];
+        for class in classes.iter().filter(|c| !has_parent.contains(*c)) {
+            roots.push(self.tree_node(class, &children, &mut vec![], &mut reached));
+        }
+        // Classes whose every ancestor is in a cycle are not below any root.
+        for class in classes {
+            if !reached.contains(class) {
+                roots.push(self.tree_node(class, &children, &mut vec![], &mut reached));
+            }
+        }
+        roots.sort_by(|a, b| self.entity_order(&a.entity, &b.entity));
+        roots
+    }
+
+    fn tree_node(
+        &self,
+        iri: &IRI<A>,
+        children: &HashMap<IRI<A>, HashSet<IRI<A>>>,
+        path: &mut Vec<IRI<A>>,
+        reached: &mut HashSet<IRI<A>>,
+    ) -> TreeNode {
+        reached.insert(iri.clone());
+        path.push(iri.clone());
+        let mut nodes = vec![];
+        for child in children.get(iri).into_iter().flatten() {
+            if !path.contains(child) {
+                nodes.push(self.tree_node(child, children, path, reached));
+            }
+        }
+        path.pop();
+        nodes.sort_by(|a, b| self.entity_order(&a.entity, &b.entity));
+        TreeNode {
+            entity: self.build_entity_display(iri.clone()),
+            children: nodes,
+        }
+    }
+
+    /// Order of entities in the index and the class tree. Ties are broken by
+    /// IRI so that the output is the same on every run.
+    fn entity_order(&self, a: &EntityDisplay, b: &EntityDisplay) -> Ordering {
+        let by = match self.settings.order.unwrap_or_default() {
+            EntityOrder::Label => a.display.to_lowercase().cmp(&b.display.to_lowercase()),
+            EntityOrder::Iri => Ordering::Equal,
+            EntityOrder::Ontology => {
+                let position = |e: &EntityDisplay| {
+                    self.document_order
+                        .get(&e.iri)
+                        .copied()
+                        .unwrap_or(usize::MAX)
+                };
+                position(a).cmp(&position(b))
+            }
+        };
+        by.then_with(|| a.iri.cmp(&b.iri))
+    }
+
+    /// Term pointed to by a replaced-by or consider annotation. OBO
+    /// ontologies often give it as a CURIE literal rather than an IRI.
+    fn replacement_display(&self, av: &AnnotationValue<A>) -> EntityDisplay {
+        let value = match av {
+            AnnotationValue::IRI(iri) => return self.build_entity_display(iri.clone()),
+            AnnotationValue::Literal(l) => l.literal().clone(),
+            AnnotationValue::AnonymousIndividual(a) => a.to_string(),
+        };
+        let expanded = match self.prefix_mapping.expand_curie_string(&value) {
+            Ok(iri) => iri,
+            Err(_) if value.starts_with("http://") || value.starts_with("https://") => {
+                value.clone()
+            }
+            Err(_) => return EntityDisplay::new(value.clone(), String::new(), value),
+        };
+        self.build_entity_display(Build::new().iri(expanded))
+    }
+
+    /// Keeps, for each annotation property with values in several languages,
+    /// the values in the most preferred one. Untagged values and values that
+    /// are not literals are always kept.
+    fn in_preferred_language(
+        &self,
+        annotations: Vec<OntologyAnnotation>,
+    ) -> Vec<OntologyAnnotation> {
+        let preferred = self.preferred_languages();
+        let mut langs: HashMap<&str, Vec<&Option<String>>> = HashMap::new();
+        for a in annotations.iter().filter(|a| a.lang.is_some()) {
+            langs.entry(&a.iri).or_default().push(&a.lang);
+        }
+        let best: HashMap<String, Option<String>> = langs
+            .into_iter()
+            .filter_map(|(iri, ls)| Some((iri.to_string(), best_language(ls, &preferred)?.clone())))
+            .collect();
+        annotations
+            .into_iter()
+            .filter(|a| a.lang.is_none() || best.get(&a.iri) == Some(&a.lang))
+            .collect()
+    }
+
+    /// Current language and, when building one site per language, links to
+    /// `page` in each of them.
+    fn insert_language_context(&self, context: &mut TeraContext, page: &str) {
+        if let Some(lang) = self.preferred_languages().first() {
+            context.insert("language", lang);
+        }
+        if self.settings.language_sites.unwrap_or(false) {
+            let root = self.settings.baseurl.as_deref().unwrap_or("/");
+            let links: Vec<LanguageLink> = self
+                .settings
+                .languages
+                .iter()
+                .flatten()
+                .map(|lang| LanguageLink {
+                    lang: lang.clone(),
+                    url: format!("{root}{lang}/{page}"),
+                    current: self.language.as_ref() == Some(lang),
+                })
+                .collect();
+            context.insert("languages", &links);
+        }
+    }
+
+    fn expression<T: Manchester>(&self, expression: T) -> ExpressionDisplay<T> {
+        let (manchester, text) = match self.settings.syntax.unwrap_or_default() {
+            ExpressionSyntax::Manchester => (
+                Some(expression.manchester_html()),
+                Some(expression.manchester_text()),
+            ),
+            ExpressionSyntax::Macros => (None, None),
+        };
+        ExpressionDisplay {
+            expression,
+            manchester,
+            text,
+            annotations: vec![],
+        }
+    }
+
+    fn annotated<T: Manchester>(
+        &self,
+        expression: T,
+        annotations: &BTreeSet<Annotation<A>>,
+    ) -> ExpressionDisplay<T> {
+        ExpressionDisplay {
+            annotations: self.build_annotation_displays(annotations),
+            ..self.expression(expression)
+        }
+    }
+
+    fn build_annotation_display(&self, ann: &Annotation<A>) -> Option<OntologyAnnotation> {
+        let value = unpack_annotation_value(&ann.av)?;
+        let display = match self.prefix_mapping.shrink_iri(ann.ap.0.as_ref()) {
+            Ok(s) => s.into(),
+            Err(_) => ann.ap.0.to_string(),
+        };
+        Some(OntologyAnnotation {
+            iri: ann.ap.0.to_string(),
+            display,
+            value,
+            lang: annotation_language(&ann.av),
+            annotations: vec![],
+        })
+    }
+
+    fn build_annotation_displays(
+        &self,
+        annotations: &BTreeSet<Annotation<A>>,
+    ) -> Vec<OntologyAnnotation> {
+        annotations
+            .iter()
+            .filter_map(|a| self.build_annotation_display(a))
+            .collect()
+    }
+
+    fn expressions<T: Manchester>(&self, expressions: Vec<T>) -> Vec<ExpressionDisplay<T>> {
+        expressions
+            .into_iter()
+            .map(|e| self.expression(e))
+            .collect()
+    }
+
+    /// Path of the page of `iri` from the site root, without extension: the
+    /// local name for the ontology namespace and `<prefix>/<local>` for the
+    /// other prefixes.
+    pub fn entity_path(&self, iri: &IRI<A>) -> Option<String> {
+        let curie = self.prefix_mapping.shrink_iri(iri).ok()?;
+        Some(curie.to_string().replace(':', "/"))
+    }
+
+    /// Search index of the declared entities, in the order of the ontology
+    /// index. Synonyms and definitions are in the preferred language, chosen
+    /// as on the entity pages.
+    pub fn search_index(&mut self) -> SearchIndex {
+        let roles = self.settings.annotations.clone().unwrap_or_default();
+        let mut definitions: HashMap<IRI<A>, RankedAnnotations> = HashMap::new();
+        let mut synonyms: HashMap<IRI<A>, Vec<OntologyAnnotation>> = HashMap::new();
+        for aa in self.closure_components_for_kind(ComponentKind::AnnotationAssertion) {
+            if let Component::AnnotationAssertion(aas) = &aa.component
+                && let AnnotationSubject::IRI(subject) = &aas.subject
+                && let Some(annotation) = self.build_annotation_display(&aas.ann)
+            {
+                match roles.role(aas.ann.ap.0.as_ref()) {
+                    Some((AnnotationRole::Definition, rank)) => keep_preferred(
+                        definitions.entry(subject.clone()).or_default(),
+                        rank,
+                        annotation,
+                    ),
+                    Some((AnnotationRole::Synonym, _)) => synonyms
+                        .entry(subject.clone())
+                        .or_default()
+                        .push(annotation),
+                    _ => (),
+                }
+            }
+        }
+        let preferred = self.preferred_languages();
+        let mut seen: HashSet<IRI<A>> = HashSet::new();
+        let mut entries: Vec<(EntityDisplay, SearchEntry)> = vec![];
+        for kind in DECLARATION_KINDS {
+            for iri in self.get_iris_for_declaration(kind) {
+                if !seen.insert(iri.clone()) {
+                    continue;
+                }
+                let display = self.build_entity_display(iri.clone());
+                let definition = definitions.get(&iri).and_then(|d| {
+                    let lang = best_language(d.keys(), &preferred)?;
+                    Some(d[lang].1.value.clone())
+                });
+                let synonyms = self
+                    .in_preferred_language(synonyms.remove(&iri).unwrap_or_default())
+                    .into_iter()
+                    .map(|s| s.value)
+                    .collect();
+                let entry = SearchEntry {
+                    url: display.url.clone(),
+                    label: self.label(&iri).cloned(),
+                    curie: self.curie(&iri),
+                    iri: iri.to_string(),
+                    kind: Kind::of_declaration(kind).name(),
+                    synonyms,
+                    definition,
+                };
+                entries.push((display, entry));
+            }
+        }
+        entries.sort_by(|a, b| self.entity_order(&a.0, &b.0));
+        SearchIndex {
+            entries: entries.into_iter().map(|(_, e)| e).collect(),
+        }
+    }
+
+    /// Asserted named superclasses and superproperties of `iri`, or its named
+    /// types for individuals.
+    fn parents(&mut self, iri: &IRI<A>) -> Vec<String> {
+        let mut parents: Vec<String> = self
+            .entity_components(iri)
+            .into_iter()
+            .filter_map(|c| match c.component {
+                Component::SubClassOf(SubClassOf {
+                    sub: ClassExpression::Class(sub),
+                    sup: ClassExpression::Class(sup),
+                }) if &sub.0 == iri => Some(sup.0),
+                Component::SubObjectPropertyOf(SubObjectPropertyOf {
+                    sub:
+                        SubObjectPropertyExpression::ObjectPropertyExpression(
+                            ObjectPropertyExpression::ObjectProperty(sub),
+                        ),
+                    sup: ObjectPropertyExpression::ObjectProperty(sup),
+                }) if &sub.0 == iri => Some(sup.0),
+                Component::SubDataPropertyOf(SubDataPropertyOf { sub, sup }) if &sub.0 == iri => {
+                    Some(sup.0)
+                }
+                Component::SubAnnotationPropertyOf(SubAnnotationPropertyOf { sub, sup })
+                    if &sub.0 == iri =>
+                {
+                    Some(sup.0)
+                }
+                Component::ClassAssertion(ClassAssertion {
+                    ce: ClassExpression::Class(ce),
+                    i: Individual::Named(NamedIndividual(i)),
+                }) if &i == iri => Some(ce.0),
+                _ => None,
+            })
+            .map(|p| p.to_string())
+            .collect();
+        parents.sort();
+        parents.dedup();
+        parents
+    }
+
+    /// Declarations, annotations and axioms about `iri` in the ontology and
+    /// its imports closure, leaving out the axioms only using it.
+    pub fn entity_components(&mut self, iri: &IRI<A>) -> Vec<AnnotatedComponent<A>> {
+        let mut components: Vec<AnnotatedComponent<A>> = self
+            .closure_components_for_iri(iri)
+            .into_iter()
+            .filter(|c| match &c.component {
+                Component::AnnotationAssertion(aa) => {
+                    matches!(&aa.subject, AnnotationSubject::IRI(s) if s == iri)
+                }
+                Component::DeclareClass(_)
+                | Component::DeclareObjectProperty(_)
+                | Component::DeclareAnnotationProperty(_)
+                | Component::DeclareDataProperty(_)
+                | Component::DeclareNamedIndividual(_)
+                | Component::DeclareDatatype(_) => true,
+                cmp => axiom_subjects(cmp).is_some_and(|s| s.contains(iri)),
+            })
+            .collect();
+        components.sort();
+        components
+    }
+
+    /// Components referencing `iri` in the ontology and its imports closure.
+    fn closure_components_for_iri(&mut self, iri: &IRI<A>) -> Vec<AnnotatedComponent<A>> {
+        let mut seen = HashSet::new();
+        std::iter::once(&mut self.ontology)
+            .chain(self.imports.iter_mut())
+            .flat_map(|o| o.components_for_iri(iri).cloned().collect::<Vec<_>>())
+            .filter(|c| seen.insert(c.clone()))
+            .collect()
+    }
+
+    /// Components of a given kind in the ontology and its imports closure.
+    fn closure_components_for_kind(&mut self, kind: ComponentKind) -> Vec<AnnotatedComponent<A>> {
+        let mut seen = HashSet::new();
+        std::iter::once(&mut self.ontology)
+            .chain(self.imports.iter_mut())
+            .flat_map(|o| o.component_for_kind(kind).cloned().collect::<Vec<_>>())
+            .filter(|c| seen.insert(c.clone()))
+            .collect()
+    }
+
+    /// An axiom as subject, Manchester syntax keyword and objects.
+    fn usage_display(&self, ann_cmp: &AnnotatedComponent<A>) -> Option<UsageDisplay> {
+        let ce = |ce: &ClassExpression<A>| self.unpack_class_expression(ce.clone());
+        let ope =
+            |ope: &ObjectPropertyExpression<A>| self.unpack_object_property_expression(ope.clone());
+        let entity = |iri: &IRI<A>| DisplayComp::Simple(self.build_entity_display(iri.clone()));
+        let individual = |i: &Individual<A>| self.unpack_individual(i.clone());
+        let n_ary = |mut v: Vec<DisplayComp>, keyword| {
+            if v.is_empty() {
+                return None;
+            }
+            let subject = v.remove(0);
+            Some((subject, keyword, v))
+        };
+        let (subject, keyword, objects) = match &ann_cmp.component {
+            Component::SubClassOf(a) => (ce(&a.sub), "SubClassOf", vec![ce(&a.sup)]),
+            Component::EquivalentClasses(a) => n_ary(a.0.iter().map(ce).collect(), "EquivalentTo")?,
+            Component::DisjointClasses(a) => n_ary(a.0.iter().map(ce).collect(), "DisjointWith")?,
+            Component::DisjointUnion(a) => (
+                entity(&a.0.0),
+                "DisjointUnionOf",
+                a.1.iter().map(ce).collect(),
+            ),
+            Component::SubObjectPropertyOf(a) => match &a.sub {
+                SubObjectPropertyExpression::ObjectPropertyExpression(sub) => {
+                    (ope(sub), "SubPropertyOf", vec![ope(&a.sup)])
+                }
+                SubObjectPropertyExpression::ObjectPropertyChain(chain) => (
+                    ope(&a.sup),
+                    "SubPropertyChain",
+                    vec![DisplayComp::Chain(GroupDisplay(
+                        chain.iter().map(ope).collect(),
+                    ))],
+                ),
+            },
+            Component::EquivalentObjectProperties(a) => {
+                n_ary(a.0.iter().map(ope).collect(), "EquivalentTo")?
+            }
+            Component::DisjointObjectProperties(a) => {
+                n_ary(a.0.iter().map(ope).collect(), "DisjointWith")?
+            }
+            Component::InverseObjectProperties(a) => {
+                (entity(&a.0.0), "InverseOf", vec![entity(&a.1.0)])
+            }
+            Component::ObjectPropertyDomain(a) => (ope(&a.ope), "Domain", vec![ce(&a.ce)]),
+            Component::ObjectPropertyRange(a) => (ope(&a.ope), "Range", vec![ce(&a.ce)]),
+            Component::SubDataPropertyOf(a) => {
+                (entity(&a.sub.0), "SubPropertyOf", vec![entity(&a.sup.0)])
+            }
+            Component::EquivalentDataProperties(a) => {
+                n_ary(a.0.iter().map(|dp| entity(&dp.0)).collect(), "EquivalentTo")?
+            }
+            Component::DisjointDataProperties(a) => {
+                n_ary(a.0.iter().map(|dp| entity(&dp.0)).collect(), "DisjointWith")?
+            }
+            Component::DataPropertyDomain(a) => (entity(&a.dp.0), "Domain", vec![ce(&a.ce)]),
+            Component::DataPropertyRange(a) => (
+                entity(&a.dp.0),
+                "Range",
+                vec![DisplayComp::DataRange(self.unpack_data_range(a.dr.clone()))],
+            ),
+            Component::DatatypeDefinition(a) => (
+                entity(&a.kind.0),
+                "EquivalentTo",
+                vec![DisplayComp::DataRange(
+                    self.unpack_data_range(a.range.clone()),
+                )],
+            ),
+            Component::HasKey(a) => (
+                ce(&a.ce),
+                "HasKey",
+                a.vpe
+                    .iter()
+                    .map(|pe| match pe {
+                        PropertyExpression::ObjectPropertyExpression(o) => ope(o),
+                        PropertyExpression::DataProperty(dp) => entity(&dp.0),
+                        PropertyExpression::AnnotationProperty(ap) => entity(&ap.0),
+                    })
+                    .collect(),
+            ),
+            Component::SameIndividual(a) => n_ary(a.0.iter().map(individual).collect(), "SameAs")?,
+            Component::DifferentIndividuals(a) => {
+                n_ary(a.0.iter().map(individual).collect(), "DifferentFrom")?
+            }
+            Component::ClassAssertion(a) => (individual(&a.i), "Types", vec![ce(&a.ce)]),
+            Component::ObjectPropertyAssertion(a) => (
+                individual(&a.from),
+                "Facts",
+                vec![DisplayComp::Fact(RelDisplay {
+                    rel: Box::new(ope(&a.ope)),
+                    ce: Box::new(individual(&a.to)),
+                })],
+            ),
+            Component::NegativeObjectPropertyAssertion(a) => (
+                individual(&a.from),
+                "Facts",
+                vec![DisplayComp::Not(Box::new(DisplayComp::Fact(RelDisplay {
+                    rel: Box::new(ope(&a.ope)),
+                    ce: Box::new(individual(&a.to)),
+                })))],
+            ),
+            Component::DataPropertyAssertion(a) => (
+                individual(&a.from),
+                "Facts",
+                vec![DisplayComp::Fact(RelDisplay {
+                    rel: Box::new(entity(&a.dp.0)),
+                    ce: Box::new(DisplayComp::Literal(
+                        self.build_literal_display(a.to.clone()),
+                    )),
+                })],
+            ),
+            Component::NegativeDataPropertyAssertion(a) => (
+                individual(&a.from),
+                "Facts",
+                vec![DisplayComp::Not(Box::new(DisplayComp::Fact(RelDisplay {
+                    rel: Box::new(entity(&a.dp.0)),
+                    ce: Box::new(DisplayComp::Literal(
+                        self.build_literal_display(a.to.clone()),
+                    )),
+                })))],
+            ),
+            Component::SubAnnotationPropertyOf(a) => {
+                (entity(&a.sub.0), "SubPropertyOf", vec![entity(&a.sup.0)])
+            }
+            _ => return None,
+        };
+        Some(UsageDisplay {
+            subject: self.expression(subject),
+            keyword,
+            objects: self.expressions(objects),
+            annotations: self.build_annotation_displays(&ann_cmp.ann),
+        })
+    }
+
+    pub fn new_with_settings(settings: Settings) -> Result<Self> {
+        let resolver = Resolver::new(&settings)?;
+        let dir = match resolver.source(&settings.ontology)? {
+            Some(d) => d,
+            None => return Err(eyre::eyre!("Expected source file")),
+        };
+        let build: Build<A> = Build::new();
+        let (o, mut prefix_mapping, declared) = read_ontology::<A, AA>(
+            &dir,
+            settings.ontology.format,
+            parser_config(&settings),
+            &build,
+        )?;
+        let closure = read_imports_closure::<A, AA>(&o, &resolver, &build)?;
+        let mut document_order: HashMap<String, usize> = HashMap::new();
+        for iri in declared.iter().chain(closure.iter().flat_map(|(_, d)| d)) {
+            let next = document_order.len();
+            document_order.entry(iri.to_string()).or_insert(next);
+        }
+        let usage = UsageIndex::new(std::iter::once(&o).chain(closure.iter().map(|(c, _)| c)));
+        let mut imports: Vec<IRIMappedOntology<A, AA>> = closure
+            .into_iter()
+            .map(|(c, _)| IRIMappedOntology::from(c))
+            .collect();
+        let mut ontology: IRIMappedOntology<A, AA> = IRIMappedOntology::from(o);
+        let roles = settings.annotations.clone().unwrap_or_default();
+        let label_map = get_label_hashmap(
+            imports.iter_mut().chain(std::iter::once(&mut ontology)),
+            &roles.properties(AnnotationRole::Label),
+        );
+        let deprecated = get_deprecated_set(
+            imports.iter_mut().chain(std::iter::once(&mut ontology)),
+            &roles.properties(AnnotationRole::Deprecated),
+        );
+        let declared = get_declared_set(imports.iter_mut().chain(std::iter::once(&mut ontology)));
+        prefix_mapping.set_default(&settings.ontology.iri);
+        if let Some(imports) = &settings.import {
+            for imp in imports.iter() {
+                if let Some(p) = &imp.suffix {
+                    let adapted = p.to_string();
+                    let exist: Vec<String> = prefix_mapping
+                        .mappings()
+                        .filter(|(_, v)| *v == &imp.iri)
+                        .map(|(p, _)| p.clone())
+                        .collect();
+                    if !exist.is_empty() {
+                        for e in exist.iter() {
+                            prefix_mapping.remove_prefix(e);
+                        }
+                    }
+                    match prefix_mapping.add_prefix(&adapted, &imp.iri) {
+                        Ok(_) => (),
+                        Err(_) => return Err(eyre::eyre!("Error when adding prefixes")),
+                    };
+                }
+            }
+        }
+        let templates: Tera = {
+            let templates_dir = match &settings.templates {
+                Some(dir) => {
+                    let mut out = String::from(dir.trim_end_matches("/"));
+                    out.push_str("/**/*.html");
+                    out
+                }
+                None => {
+                    return Err(eyre::eyre!(
+                        "Templates not defined, set templates directory"
+                    ));
+                }
+            };
+            let mut tera = match Tera::new(&templates_dir) {
+                Ok(t) => t,
+                Err(e) => return Err(eyre::eyre!("Parsing error(s): {}", e)),
+            };
+            tera.autoescape_on(vec![".html", ".sql"]);
+            tera
+        };
+        Ok(OntologyRender {
+            ontology,
+            imports,
+            prefix_mapping,
             label_map,
+            deprecated,
+            declared,
+            document_order,
             settings,
+            language: None,
             templates,
+            usage,
         })
     }
 }
@@ -904,6 +2227,65 @@ fn unpack_literal<A: ForIRI>(l: Literal<A>) -> String {
     }
 }
 
+/// Whether `i` is the named individual `iri`.
+fn names_individual<A: ForIRI>(i: &Individual<A>, iri: &IRI<A>) -> bool {
+    matches!(i, Individual::Named(ni) if &ni.0 == iri)
+}
+
+/// Whether `ce` is the named class `iri`.
+fn names_class<A: ForIRI>(ce: &ClassExpression<A>, iri: &IRI<A>) -> bool {
+    matches!(ce, ClassExpression::Class(cl) if &cl.0 == iri)
+}
+
+/// Whether `ope` is the named property `iri` rather than its inverse.
+fn names_property<A: ForIRI>(ope: &ObjectPropertyExpression<A>, iri: &IRI<A>) -> bool {
+    matches!(ope, ObjectPropertyExpression::ObjectProperty(op) if &op.0 == iri)
+}
+
+/// Characteristic of the property `iri` stated by a component, if any.
+/// Axioms on the inverse of `iri` are read as characteristics of `iri`.
+fn characteristic<A: ForIRI>(cmp: &Component<A>, iri: &IRI<A>) -> Option<Characteristic> {
+    let (ope, c) = match cmp {
+        Component::FunctionalObjectProperty(a) => (&a.0, Characteristic::Functional),
+        Component::InverseFunctionalObjectProperty(a) => (&a.0, Characteristic::InverseFunctional),
+        Component::TransitiveObjectProperty(a) => (&a.0, Characteristic::Transitive),
+        Component::SymmetricObjectProperty(a) => (&a.0, Characteristic::Symmetric),
+        Component::AsymmetricObjectProperty(a) => (&a.0, Characteristic::Asymmetric),
+        Component::ReflexiveObjectProperty(a) => (&a.0, Characteristic::Reflexive),
+        Component::IrreflexiveObjectProperty(a) => (&a.0, Characteristic::Irreflexive),
+        Component::FunctionalDataProperty(a) if &a.0.0 == iri => {
+            return Some(Characteristic::Functional);
+        }
+        _ => return None,
+    };
+    match ope {
+        ope if names_property(ope, iri) => Some(c),
+        ObjectPropertyExpression::InverseObjectProperty(op) if &op.0 == iri => Some(match c {
+            Characteristic::Functional => Characteristic::InverseFunctional,
+            Characteristic::InverseFunctional => Characteristic::Functional,
+            c => c,
+        }),
+        _ => None,
+    }
+}
+
+/// Manchester syntax keyword for a facet, e.g. `>=` for `xsd:minInclusive`.
+fn facet_symbol(f: &Facet) -> &'static str {
+    match f {
+        Facet::Length => "length",
+        Facet::MinLength => "minLength",
+        Facet::MaxLength => "maxLength",
+        Facet::Pattern => "pattern",
+        Facet::MinInclusive => ">=",
+        Facet::MinExclusive => ">",
+        Facet::MaxInclusive => "<=",
+        Facet::MaxExclusive => "<",
+        Facet::TotalDigits => "totalDigits",
+        Facet::FractionDigits => "fractionDigits",
+        Facet::LangRange => "langRange",
+    }
+}
+
 fn unpack_annotation_value<A: ForIRI>(av: &AnnotationValue<A>) -> Option<String> {
     match &av {
         AnnotationValue::AnonymousIndividual(_) => None,
@@ -919,30 +2301,157 @@ fn unpack_annotation_value<A: ForIRI>(av: &AnnotationValue<A>) -> Option<String>
     }
 }
 
-fn get_label_hashmap<A, AA>(ontology: &mut IRIMappedOntology<A, AA>) -> HashMap<IRI<A>, String>
+/// Links to the serializations written next to a page, `stem` being its URL
+/// without extension.
+fn export_links(stem: &str) -> Vec<ExportLink> {
+    ExportFormat::ALL
+        .into_iter()
+        .map(|f| ExportLink {
+            format: f.name(),
+            url: format!("{stem}.{}", f.extension()),
+        })
+        .collect()
+}
+
+/// Keeps in `values` the annotation of the most preferred property for its
+/// language.
+fn keep_preferred(values: &mut RankedAnnotations, rank: usize, annotation: OntologyAnnotation) {
+    match values.entry(annotation.lang.clone()) {
+        Entry::Occupied(mut o) if rank < o.get().0 => {
+            o.insert((rank, annotation));
+        }
+        Entry::Occupied(_) => (),
+        Entry::Vacant(v) => {
+            v.insert((rank, annotation));
+        }
+    }
+}
+
+fn annotation_language<A: ForIRI>(av: &AnnotationValue<A>) -> Option<String> {
+    match av {
+        AnnotationValue::Literal(Literal::Language { lang, .. }) => Some(lang.clone()),
+        _ => None,
+    }
+}
+
+/// The most preferred of `langs`: the first of `preferred` (or one of its
+/// regional variants, `en-GB` for `en`), then untagged, then the first tag in
+/// alphabetical order.
+fn best_language<'a>(
+    langs: impl IntoIterator<Item = &'a Option<String>>,
+    preferred: &[String],
+) -> Option<&'a Option<String>> {
+    let rank = |l: &Option<String>| match l {
+        Some(l) => preferred
+            .iter()
+            .enumerate()
+            .find_map(|(i, p)| {
+                if l.eq_ignore_ascii_case(p) {
+                    Some(2 * i)
+                } else if l.len() > p.len()
+                    && l[..p.len()].eq_ignore_ascii_case(p)
+                    && l[p.len()..].starts_with('-')
+                {
+                    Some(2 * i + 1)
+                } else {
+                    None
+                }
+            })
+            .unwrap_or(2 * preferred.len() + 1),
+        None => 2 * preferred.len(),
+    };
+    langs
+        .into_iter()
+        .min_by(|a, b| rank(a).cmp(&rank(b)).then_with(|| a.cmp(b)))
+}
+
+/// Entities of `ontologies` annotated as deprecated by one of
+/// `deprecated_properties`, e.g. `owl:deprecated true`.
+fn get_deprecated_set<'a, A, AA>(
+    ontologies: impl IntoIterator<Item = &'a mut IRIMappedOntology<A, AA>>,
+    deprecated_properties: &[String],
+) -> HashSet<IRI<A>>
+where
+    A: ForIRI + 'a,
+    AA: ForIndex<A> + 'a,
+{
+    let mut deprecated = HashSet::new();
+    for ontology in ontologies {
+        for aa in ontology.component_for_kind(ComponentKind::AnnotationAssertion) {
+            if let Component::AnnotationAssertion(aas) = &aa.component
+                && let AnnotationSubject::IRI(iri) = &aas.subject
+                && let AnnotationValue::Literal(literal) = &aas.ann.av
+                && matches!(literal.literal().as_str(), "true" | "1")
+                && deprecated_properties
+                    .iter()
+                    .any(|p| p == aas.ann.ap.0.as_ref())
+            {
+                deprecated.insert(iri.clone());
+            }
+        }
+    }
+    deprecated
+}
+
+/// Entities declared in `ontologies`, with any kind.
+fn get_declared_set<'a, A, AA>(
+    ontologies: impl IntoIterator<Item = &'a mut IRIMappedOntology<A, AA>>,
+) -> HashSet<IRI<A>>
 where
-    A: ForIRI,
-    AA: ForIndex<A>,
+    A: ForIRI + 'a,
+    AA: ForIndex<A> + 'a,
 {
-    let mut label_map: HashMap<IRI<A>, String> = HashMap::new();
-
-    for aa in ontology.component_for_kind(ComponentKind::AnnotationAssertion) {
-        match &aa.component {
-            Component::AnnotationAssertion(aas) => match &aas.subject {
-                AnnotationSubject::IRI(iri) => match aas.ann.ap.0.as_ref() {
-                    "http://www.w3.org/2000/01/rdf-schema#label" => match &aas.ann.av {
-                        AnnotationValue::Literal(literal) => {
-                            label_map.insert(iri.clone(), literal.literal().clone());
-                        }
-                        _ => (),
-                    },
-                    _ => (),
-                },
-                AnnotationSubject::AnonymousIndividual(_) => (),
-            },
-            _ => (),
+    let mut declared = HashSet::new();
+    for ontology in ontologies {
+        for kind in DECLARATION_KINDS {
+            for dc in ontology.component_for_kind(kind) {
+                let iri = match &dc.component {
+                    Component::DeclareClass(dc) => &dc.0.0,
+                    Component::DeclareNamedIndividual(ni) => &ni.0.0,
+                    Component::DeclareDataProperty(ddp) => &ddp.0.0,
+                    Component::DeclareObjectProperty(dop) => &dop.0.0,
+                    Component::DeclareAnnotationProperty(dap) => &dap.0.0,
+                    Component::DeclareDatatype(ddt) => &ddt.0.0,
+                    _ => continue,
+                };
+                declared.insert(iri.clone());
+            }
         }
     }
+    declared
+}
 
+/// Labels of the entities of `ontologies`, taken from `label_properties`.
+/// In each language the label of the most preferred property is kept, and
+/// among those of the same property the one from the last ontology.
+fn get_label_hashmap<'a, A, AA>(
+    ontologies: impl IntoIterator<Item = &'a mut IRIMappedOntology<A, AA>>,
+    label_properties: &[String],
+) -> LabelMap<A>
+where
+    A: ForIRI + 'a,
+    AA: ForIndex<A> + 'a,
+{
+    let mut ranked: HashMap<(IRI<A>, Option<String>), (usize, String)> = HashMap::new();
+    for ontology in ontologies {
+        for aa in ontology.component_for_kind(ComponentKind::AnnotationAssertion) {
+            if let Component::AnnotationAssertion(aas) = &aa.component
+                && let AnnotationSubject::IRI(iri) = &aas.subject
+                && let AnnotationValue::Literal(literal) = &aas.ann.av
+                && let Some(rank) = label_properties
+                    .iter()
+                    .position(|p| p == aas.ann.ap.0.as_ref())
+            {
+                let key = (iri.clone(), annotation_language(&aas.ann.av));
+                if ranked.get(&key).is_none_or(|(r, _)| rank <= *r) {
+                    ranked.insert(key, (rank, literal.literal().clone()));
+                }
+            }
+        }
+    }
+    let mut label_map: LabelMap<A> = HashMap::new();
+    for ((iri, lang), (_, label)) in ranked {
+        label_map.entry(iri).or_default().insert(lang, label);
+    }
     label_map
 }
@@ -0,0 +1,199 @@
+use serde::Serialize;
+use serde_json::json;
+
+/// Version of `search.json`, increased on incompatible changes.
+pub const SEARCH_SCHEMA_VERSION: u32 = 1;
+
+/// Order of the fields of an entry in `search.json`.
+const FIELDS: [&str; 7] = [
+    "url",
+    "label",
+    "curie",
+    "iri",
+    "kind",
+    "synonyms",
+    "definition",
+];
+
+/// An entity as found by search.
+#[derive(Serialize, Debug, Clone)]
+pub struct SearchEntry {
+    pub url: String,
+    pub label: Option<String>,
+    pub curie: String,
+    pub iri: String,
+    pub kind: &'static str,
+    pub synonyms: Vec<String>,
+    pub definition: Option<String>,
+}
+
+/// Labels, synonyms, definitions, CURIEs and IRIs of the entities of an
+/// ontology, written to `search.json` for `static/scripts/search.js` and
+/// queried by `/search?q=` in serve mode. Both match the same way, see
+/// [`SearchIndex::search`].
+#[derive(Debug, Default)]
+pub struct SearchIndex {
+    pub entries: Vec<SearchEntry>,
+}
+
+impl SearchIndex {
+    /// The index as compact JSON, with each entry as an array in the order
+    /// of `fields`.
+    pub fn to_json(&self) -> String {
+        let entries: Vec<serde_json::Value> = self
+            .entries
+            .iter()
+            .map(|e| {
+                json!([
+                    e.url,
+                    e.label,
+                    e.curie,
+                    e.iri,
+                    e.kind,
+                    e.synonyms,
+                    e.definition
+                ])
+            })
+            .collect();
+        json!({
+            "schema_version": SEARCH_SCHEMA_VERSION,
+            "fields": FIELDS,
+            "entries": entries,
+        })
+        .to_string()
+    }
+
+    /// Entries matching every word of `query`, best first.
+    ///
+    /// A word matches an entry when it is a word of its label, synonyms or
+    /// CURIE, or the prefix of one, or within one or two typos of one for
+    /// words of at least four or eight letters without digits. It also
+    /// matches a word or prefix of a word of the definition, or, when of at
+    /// least three letters, part of the local name of the IRI, after its last
+    /// `#` or `/`. Label matches rank highest, and definition matches lowest.
+    /// Ties go to the shortest name.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<&SearchEntry> {
+        let terms = words(query);
+        if terms.is_empty() {
+            return vec![];
+        }
+        let query = terms.join(" ");
+        let mut found: Vec<(u32, &SearchEntry)> = self
+            .entries
+            .iter()
+            .filter_map(|e| score(e, &terms, &query).map(|s| (s, e)))
+            .collect();
+        found.sort_by(|a, b| {
+            b.0.cmp(&a.0)
+                .then_with(|| name(a.1).chars().count().cmp(&name(b.1).chars().count()))
+                .then_with(|| name(a.1).cmp(name(b.1)))
+                .then_with(|| a.1.iri.cmp(&b.1.iri))
+        });
+        found.into_iter().take(limit).map(|(_, e)| e).collect()
+    }
+}
+
+fn score(entry: &SearchEntry, terms: &[String], query: &str) -> Option<u32> {
+    let label = words(entry.label.as_deref().unwrap_or_default());
+    let names: Vec<String> = entry
+        .synonyms
+        .iter()
+        .flat_map(|s| words(s))
+        .chain(words(&entry.curie))
+        .collect();
+    let definition = words(entry.definition.as_deref().unwrap_or_default());
+    let iri = local_name(&entry.iri).to_lowercase();
+    let mut total = 0;
+    for term in terms {
+        let best = [
+            word_score(term, &label, true) * 2,
+            word_score(term, &names, true),
+            if term.chars().count() >= 3 && iri.contains(term.as_str()) {
+                4
+            } else {
+                0
+            },
+            word_score(term, &definition, false) / 5,
+        ]
+        .into_iter()
+        .max()
+        .unwrap_or_default();
+        if best == 0 {
+            return None;
+        }
+        total += best;
+    }
+    let label = label.join(" ");
+    if label == query {
+        total += 20;
+    } else if label.starts_with(&format!("{query} ")) {
+        total += 10;
+    }
+    Some(total)
+}
+
+/// 10 for a word equal to `term`, 6 for a prefix match, and 3 for a fuzzy
+/// match when `fuzzy`.
+fn word_score(term: &str, words: &[String], fuzzy: bool) -> u32 {
+    let edits = match term.chars().count() {
+        _ if term.chars().any(char::is_numeric) => 0,
+        0..4 => 0,
+        4..8 => 1,
+        _ => 2,
+    };
+    words
+        .iter()
+        .map(|w| {
+            if w == term {
+                10
+            } else if w.starts_with(term) {
+                6
+            } else if fuzzy && edits > 0 && within_edits(term, w, edits) {
+                3
+            } else {
+                0
+            }
+        })
+        .max()
+        .unwrap_or_default()
+}
+
+/// Whether the Levenshtein distance between `a` and `b` is at most `max`.
+fn within_edits(a: &str, b: &str, max: usize) -> bool {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > max {
+        return false;
+    }
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        if current.iter().min().is_some_and(|d| *d > max) {
+            return false;
+        }
+        previous = current;
+    }
+    previous[b.len()] <= max
+}
+
+/// Lowercase alphanumeric words of `text`.
+fn words(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Part of `iri` after its last `#` or `/`.
+fn local_name(iri: &str) -> &str {
+    iri.rsplit(['#', '/']).next().unwrap_or(iri)
+}
+
+fn name(entry: &SearchEntry) -> &str {
+    entry.label.as_deref().unwrap_or(&entry.curie)
+}
@@ -0,0 +1,346 @@
+use eyre::Result;
+use horned_owl::model::{ArcStr, IRI};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Cursor;
+use std::path::{Component, Path, PathBuf};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::export::ExportFormat;
+use crate::render_html::{ArcOntologyRender, DECLARATION_KINDS, IRIMappedRenderHTML};
+use crate::search::{SEARCH_SCHEMA_VERSION, SearchIndex};
+
+type HttpResponse = Response<Cursor<Vec<u8>>>;
+
+/// Number of results of `/search` when not given by `limit`.
+const SEARCH_LIMIT: usize = 20;
+
+/// What a request for an entity or the ontology is answered with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Representation {
+    Html,
+    Json,
+    Export(ExportFormat),
+}
+
+impl Representation {
+    fn extension(&self) -> &'static str {
+        match self {
+            Representation::Html => "html",
+            Representation::Json => "json",
+            Representation::Export(f) => f.extension(),
+        }
+    }
+}
+
+/// Serves the ontology over HTTP, rendering pages on request.
+///
+/// Entity IRIs are answered following the Cool URIs recipe: a request for
+/// `/<id>` (slash IRIs) or for the ontology document at `/` (hash IRIs) is
+/// redirected with `303 See Other` to the page or serialization matching its
+/// `Accept` header, such as `/<id>.html` or `/<id>.ttl`.
+///
+/// `/search?q=<query>` answers the entities matching the query as JSON, from
+/// the same index as `search.json`.
+pub fn serve(or: &mut ArcOntologyRender, address: &str) -> Result<()> {
+    let server =
+        Server::http(address).map_err(|e| eyre::eyre!("Could not listen on {address}: {e}"))?;
+    let entities = entity_paths(or);
+    let index = or.search_index();
+    let base = or.settings.base_path();
+    println!(
+        "Serving {} on http://{address}{base}",
+        or.settings.ontology.iri
+    );
+    for request in server.incoming_requests() {
+        let response = handle(or, &entities, &index, &base, &request);
+        println!(
+            "{} {} {}",
+            request.method(),
+            request.url(),
+            response.status_code().0
+        );
+        if let Err(e) = request.respond(response) {
+            eprintln!("Could not send response: {e}");
+        }
+    }
+    Ok(())
+}
+
+fn handle(
+    or: &mut ArcOntologyRender,
+    entities: &HashMap<String, IRI<ArcStr>>,
+    index: &SearchIndex,
+    base: &str,
+    request: &Request,
+) -> HttpResponse {
+    if !matches!(request.method(), Method::Get | Method::Head) {
+        return text(405, "Method not allowed");
+    }
+    let url = request.url();
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let path = path.split('#').next().unwrap_or_default();
+    let Some(path) = path.strip_prefix(base) else {
+        return text(404, "Not found");
+    };
+    let accept = request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("Accept"))
+        .map(|h| h.value.as_str());
+    let rendered = match path {
+        "" => return redirect(base, "ontology", accept),
+        "index.html" => or.render_metadata_html(None).map(html),
+        "tree.json" => or
+            .render_tree_json(None)
+            .map(|t| content(200, "application/json", t.into_bytes())),
+        "entities.json" => or.render_entities_json().map(json),
+        "search.json" => Ok(json(index.to_json())),
+        "search" => return search(index, query),
+        _ if path.starts_with("static/") => return static_file(or, path),
+        _ => {
+            let (name, extension) = match path.rsplit_once('.') {
+                Some((n, e)) if !n.is_empty() && !e.contains('/') => (n, Some(e)),
+                _ => (path, None),
+            };
+            if name == "ontology"
+                && let Some(format) = extension.and_then(ExportFormat::from_extension)
+            {
+                or.render_ontology_export(format)
+                    .map(|b| content(200, format.media_type(), b))
+            } else if let Some(iri) = entities.get(name) {
+                match extension {
+                    None => return redirect(base, name, accept),
+                    Some("html") => or.render_declaration_iri_html(iri).map(html),
+                    Some("json") => or.render_declaration_iri_json(iri).map(json),
+                    Some(e) => match ExportFormat::from_extension(e) {
+                        Some(format) => or
+                            .render_declaration_iri_export(iri, format)
+                            .map(|b| content(200, format.media_type(), b)),
+                        None => return text(404, "Not found"),
+                    },
+                }
+            } else if let Some(import) =
+                or.settings.import.clone().into_iter().flatten().find(|i| {
+                    i.suffix.as_ref().is_some_and(|s| {
+                        path == format!("{s}/") || path == format!("{s}/index.html")
+                    })
+                })
+            {
+                or.render_metadata_html(Some(&import)).map(html)
+            } else {
+                return text(404, "Not found");
+            }
+        }
+    };
+    match rendered {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("Could not render {url}: {e:?}");
+            text(500, "Internal server error")
+        }
+    }
+}
+
+/// Page paths of every declared entity, as used in the built site.
+fn entity_paths(or: &mut ArcOntologyRender) -> HashMap<String, IRI<ArcStr>> {
+    let mut entities = HashMap::new();
+    for kind in DECLARATION_KINDS {
+        for iri in or.get_iris_for_declaration(kind) {
+            if let Some(path) = or.entity_path(&iri) {
+                entities.insert(path, iri.clone());
+            }
+        }
+    }
+    entities
+}
+
+/// Answers `/search` with the entries matching the `q` parameter, up to
+/// `limit`.
+fn search(index: &SearchIndex, query: &str) -> HttpResponse {
+    let mut q = None;
+    let mut limit = SEARCH_LIMIT;
+    for (key, value) in
+        form_urlencoded::parse(query.split('#').next().unwrap_or_default().as_bytes())
+    {
+        match key.as_ref() {
+            "q" => q = Some(value.into_owned()),
+            "limit" => match value.parse() {
+                Ok(l) => limit = l,
+                Err(_) => return text(400, "Invalid limit"),
+            },
+            _ => {}
+        }
+    }
+    let Some(q) = q else {
+        return text(400, "Missing query parameter q");
+    };
+    let results = serde_json::json!({
+        "schema_version": SEARCH_SCHEMA_VERSION,
+        "query": q,
+        "results": index.search(&q, limit),
+    });
+    json(results.to_string())
+}
+
+/// Picks the representation with the highest quality in an `Accept` header.
+/// Browsers, and clients without a preference, get HTML.
+fn negotiate(accept: Option<&str>) -> Option<Representation> {
+    let Some(accept) = accept.filter(|a| !a.trim().is_empty()) else {
+        return Some(Representation::Html);
+    };
+    let mut ranges: Vec<(&str, f32)> = accept
+        .split(',')
+        .map(|range| {
+            let mut parts = range.split(';').map(str::trim);
+            let media_type = parts.next().unwrap_or_default();
+            let q = parts
+                .find_map(|p| p.strip_prefix("q="))
+                .and_then(|q| q.parse().ok())
+                .unwrap_or(1.0);
+            (media_type, q)
+        })
+        .filter(|(_, q)| *q > 0.0)
+        .collect();
+    ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranges
+        .into_iter()
+        .find_map(|(media_type, _)| match media_type {
+            "text/html" | "application/xhtml+xml" | "text/*" | "*/*" => Some(Representation::Html),
+            "application/json" => Some(Representation::Json),
+            m => ExportFormat::from_media_type(m).map(Representation::Export),
+        })
+}
+
+fn redirect(base: &str, name: &str, accept: Option<&str>) -> HttpResponse {
+    let Some(representation) = negotiate(accept) else {
+        return text(406, "Not acceptable").with_header(header("Vary", "Accept"));
+    };
+    let location = match (name, representation) {
+        ("ontology", Representation::Html) => base.to_string() + "index.html",
+        ("ontology", Representation::Json) => base.to_string() + "entities.json",
+        _ => format!("{base}{name}.{}", representation.extension()),
+    };
+    Response::from_data(Vec::new())
+        .with_status_code(303)
+        .with_header(header("Location", &location))
+        .with_header(header("Vary", "Accept"))
+}
+
+fn static_file(or: &ArcOntologyRender, path: &str) -> HttpResponse {
+    let Some(file) = or
+        .settings
+        .assets
+        .as_deref()
+        .and_then(|assets| asset_path(assets, path))
+    else {
+        return text(404, "Not found");
+    };
+    let Ok(body) = fs::read(&file) else {
+        return text(404, "Not found");
+    };
+    let media_type = match file.extension().and_then(|e| e.to_str()) {
+        Some("css") => "text/css",
+        Some("js") => "text/javascript",
+        Some("json") => "application/json",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("ico") => "image/x-icon",
+        Some("woff2") => "font/woff2",
+        _ => "application/octet-stream",
+    };
+    content(200, media_type, body)
+}
+
+/// File of the `assets` directory served at `static/<path>`. Only plain names
+/// are allowed, so that roots and `..` cannot leave the directory.
+fn asset_path(assets: &str, path: &str) -> Option<PathBuf> {
+    let relative = Path::new(path.trim_start_matches("static/"));
+    relative
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)))
+        .then(|| Path::new(assets).join(relative))
+}
+
+fn html(page: String) -> HttpResponse {
+    content(200, "text/html; charset=utf-8", page.into_bytes())
+}
+
+fn json(document: String) -> HttpResponse {
+    content(200, "application/json", document.into_bytes())
+}
+
+fn text(status: u16, message: &str) -> HttpResponse {
+    content(
+        status,
+        "text/plain; charset=utf-8",
+        message.as_bytes().to_vec(),
+    )
+}
+
+fn content(status: u16, media_type: &str, body: Vec<u8>) -> HttpResponse {
+    Response::from_data(body)
+        .with_status_code(status)
+        .with_header(header("Content-Type", media_type))
+}
+
+fn header(field: &str, value: &str) -> Header {
+    Header::from_bytes(field.as_bytes(), value.as_bytes()).expect("Invalid header")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negotiate_prefers_the_highest_quality() {
+        assert_eq!(negotiate(None), Some(Representation::Html));
+        assert_eq!(negotiate(Some(" ")), Some(Representation::Html));
+        assert_eq!(
+            negotiate(Some("text/turtle;q=0.5, application/json")),
+            Some(Representation::Json)
+        );
+        assert_eq!(
+            negotiate(Some("application/json;q=0.2, text/turtle; q=0.9")),
+            Some(Representation::Export(ExportFormat::Turtle))
+        );
+        assert_eq!(
+            negotiate(Some("text/html;q=0, application/owl+xml")),
+            Some(Representation::Export(ExportFormat::Owx))
+        );
+        assert_eq!(
+            negotiate(Some("image/png, */*;q=0.1")),
+            Some(Representation::Html)
+        );
+        assert_eq!(negotiate(Some("image/png, text/html;q=0")), None);
+    }
+
+    #[test]
+    fn redirects_or_refuses_unacceptable_requests() {
+        let response = redirect("/", "A", Some("application/rdf+xml"));
+        assert_eq!(response.status_code().0, 303);
+        let location = response
+            .headers()
+            .iter()
+            .find(|h| h.field.equiv("Location"))
+            .map(|h| h.value.to_string());
+        assert_eq!(location.as_deref(), Some("/A.rdf"));
+        assert_eq!(redirect("/", "A", Some("image/png")).status_code().0, 406);
+    }
+
+    #[test]
+    fn asset_paths_stay_in_the_assets_directory() {
+        assert_eq!(
+            asset_path("static", "static/scripts/search.js"),
+            Some(PathBuf::from("static/scripts/search.js"))
+        );
+        for path in [
+            "static//etc/passwd",
+            "static/../Cargo.toml",
+            "static/scripts/../../Cargo.toml",
+            "static/./scripts/search.js",
+        ] {
+            assert_eq!(asset_path("static", path), None, "{path}");
+        }
+    }
+}
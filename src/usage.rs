@@ -0,0 +1,124 @@
+use horned_owl::model::{
+    AnnotatedComponent, ClassExpression, Component, DataPropertyAssertion, ForIRI, IRI, Individual,
+    NegativeDataPropertyAssertion, ObjectPropertyExpression, SubObjectPropertyExpression,
+};
+use horned_owl::ontology::set::SetOntology;
+use horned_owl::visitor::immutable::Walk;
+use horned_owl::visitor::immutable::entity::IRIExtract;
+use std::collections::{HashMap, HashSet};
+
+/// Reverse index from an IRI to the axioms using it, built once when the
+/// ontologies are loaded.
+///
+/// An axiom counts as a usage of every IRI it mentions, however deeply nested,
+/// except for the entities it is about (the subclass of a `SubClassOf`, the
+/// property of a domain, the subject of an assertion…), whose pages already
+/// show it.
+#[derive(Debug)]
+pub struct UsageIndex<A: ForIRI> {
+    usages: HashMap<IRI<A>, Vec<AnnotatedComponent<A>>>,
+}
+
+impl<A: ForIRI> UsageIndex<A> {
+    pub fn new<'a>(ontologies: impl IntoIterator<Item = &'a SetOntology<A>>) -> Self
+    where
+        A: 'a,
+    {
+        let mut usages: HashMap<IRI<A>, Vec<AnnotatedComponent<A>>> = HashMap::new();
+        let mut seen = HashSet::new();
+        for cmp in ontologies.into_iter().flat_map(|o| o.iter()) {
+            let Some(subjects) = axiom_subjects(&cmp.component) else {
+                continue;
+            };
+            if !seen.insert(cmp) {
+                continue;
+            }
+            let mut walk = Walk::new(IRIExtract::default());
+            walk.component(&cmp.component);
+            let iris: HashSet<IRI<A>> = walk.into_visit().into_vec().into_iter().collect();
+            for iri in iris {
+                if !subjects.contains(&iri) {
+                    usages.entry(iri).or_default().push(cmp.clone());
+                }
+            }
+        }
+        for axioms in usages.values_mut() {
+            axioms.sort();
+        }
+        UsageIndex { usages }
+    }
+
+    /// Axioms using `iri`, other than those about it.
+    pub fn get(&self, iri: &IRI<A>) -> &[AnnotatedComponent<A>] {
+        self.usages.get(iri).map(Vec::as_slice).unwrap_or_default()
+    }
+}
+
+/// Entities an axiom is about, or `None` for components that are not
+/// indexed (declarations, annotations and ontology metadata).
+pub(crate) fn axiom_subjects<A: ForIRI>(cmp: &Component<A>) -> Option<Vec<IRI<A>>> {
+    let subjects = match cmp {
+        Component::SubClassOf(a) => [&a.sub, &a.sup].into_iter().filter_map(class_iri).collect(),
+        Component::EquivalentClasses(a) => a.0.iter().filter_map(class_iri).collect(),
+        Component::DisjointClasses(a) => a.0.iter().filter_map(class_iri).collect(),
+        Component::DisjointUnion(a) => vec![a.0.0.clone()],
+        Component::SubObjectPropertyOf(a) => {
+            let mut v: Vec<IRI<A>> = property_iri(&a.sup).into_iter().collect();
+            if let SubObjectPropertyExpression::ObjectPropertyExpression(ope) = &a.sub {
+                v.extend(property_iri(ope));
+            }
+            v
+        }
+        Component::EquivalentObjectProperties(a) => a.0.iter().filter_map(property_iri).collect(),
+        Component::DisjointObjectProperties(a) => a.0.iter().filter_map(property_iri).collect(),
+        Component::InverseObjectProperties(a) => vec![a.0.0.clone(), a.1.0.clone()],
+        Component::ObjectPropertyDomain(a) => property_iri(&a.ope).into_iter().collect(),
+        Component::ObjectPropertyRange(a) => property_iri(&a.ope).into_iter().collect(),
+        Component::SubDataPropertyOf(a) => vec![a.sub.0.clone(), a.sup.0.clone()],
+        Component::EquivalentDataProperties(a) => a.0.iter().map(|dp| dp.0.clone()).collect(),
+        Component::DisjointDataProperties(a) => a.0.iter().map(|dp| dp.0.clone()).collect(),
+        Component::DataPropertyDomain(a) => vec![a.dp.0.clone()],
+        Component::DataPropertyRange(a) => vec![a.dp.0.clone()],
+        Component::DatatypeDefinition(a) => vec![a.kind.0.clone()],
+        Component::HasKey(a) => class_iri(&a.ce).into_iter().collect(),
+        Component::SameIndividual(a) => a.0.iter().filter_map(individual_iri).collect(),
+        Component::DifferentIndividuals(a) => a.0.iter().filter_map(individual_iri).collect(),
+        Component::ClassAssertion(a) => individual_iri(&a.i).into_iter().collect(),
+        Component::ObjectPropertyAssertion(a) => [&a.from, &a.to]
+            .into_iter()
+            .filter_map(individual_iri)
+            .collect(),
+        Component::NegativeObjectPropertyAssertion(a) => [&a.from, &a.to]
+            .into_iter()
+            .filter_map(individual_iri)
+            .collect(),
+        Component::DataPropertyAssertion(DataPropertyAssertion { from, .. })
+        | Component::NegativeDataPropertyAssertion(NegativeDataPropertyAssertion {
+            from, ..
+        }) => individual_iri(from).into_iter().collect(),
+        Component::SubAnnotationPropertyOf(a) => vec![a.sub.0.clone(), a.sup.0.clone()],
+        _ => return None,
+    };
+    Some(subjects)
+}
+
+fn class_iri<A: ForIRI>(ce: &ClassExpression<A>) -> Option<IRI<A>> {
+    match ce {
+        ClassExpression::Class(cl) => Some(cl.0.clone()),
+        _ => None,
+    }
+}
+
+fn property_iri<A: ForIRI>(ope: &ObjectPropertyExpression<A>) -> Option<IRI<A>> {
+    match ope {
+        ObjectPropertyExpression::ObjectProperty(op) => Some(op.0.clone()),
+        ObjectPropertyExpression::InverseObjectProperty(_) => None,
+    }
+}
+
+fn individual_iri<A: ForIRI>(i: &Individual<A>) -> Option<IRI<A>> {
+    match i {
+        Individual::Named(ni) => Some(ni.0.clone()),
+        Individual::Anonymous(_) => None,
+    }
+}
@@ -0,0 +1,155 @@
+// Search box of the pages. Queries the `search.json` index written by
+// `hyppo build`, matching like `SearchIndex::search` in src/search.rs so that
+// results agree with `/search?q=` in serve mode.
+(function () {
+    const LIMIT = 20;
+    const script = document.currentScript;
+    let index = null;
+
+    function words(text) {
+        return (text || "").toLowerCase().match(/[\p{L}\p{N}]+/gu) || [];
+    }
+
+    function withinEdits(a, b, max) {
+        a = Array.from(a);
+        b = Array.from(b);
+        if (Math.abs(a.length - b.length) > max) {
+            return false;
+        }
+        let previous = Array.from({ length: b.length + 1 }, (_, j) => j);
+        for (let i = 0; i < a.length; i++) {
+            const current = [i + 1];
+            for (let j = 0; j < b.length; j++) {
+                const substitution = previous[j] + (a[i] === b[j] ? 0 : 1);
+                current.push(
+                    Math.min(substitution, previous[j + 1] + 1, current[j] + 1),
+                );
+            }
+            if (Math.min(...current) > max) {
+                return false;
+            }
+            previous = current;
+        }
+        return previous[b.length] <= max;
+    }
+
+    function wordScore(term, candidates, fuzzy) {
+        const length = Array.from(term).length;
+        let edits = 0;
+        if (!/\p{N}/u.test(term)) {
+            edits = length < 4 ? 0 : length < 8 ? 1 : 2;
+        }
+        let best = 0;
+        for (const w of candidates) {
+            if (w === term) {
+                best = Math.max(best, 10);
+            } else if (w.startsWith(term)) {
+                best = Math.max(best, 6);
+            } else if (fuzzy && edits > 0 && withinEdits(term, w, edits)) {
+                best = Math.max(best, 3);
+            }
+        }
+        return best;
+    }
+
+    function score(entry, terms, query) {
+        const label = words(entry.label);
+        const names = entry.synonyms.flatMap(words).concat(words(entry.curie));
+        const definition = words(entry.definition);
+        const iri = entry.iri.split(/[#/]/).pop().toLowerCase();
+        let total = 0;
+        for (const term of terms) {
+            const best = Math.max(
+                wordScore(term, label, true) * 2,
+                wordScore(term, names, true),
+                Array.from(term).length >= 3 && iri.includes(term) ? 4 : 0,
+                Math.floor(wordScore(term, definition, false) / 5),
+            );
+            if (best === 0) {
+                return null;
+            }
+            total += best;
+        }
+        const joined = label.join(" ");
+        if (joined === query) {
+            total += 20;
+        } else if (joined.startsWith(query + " ")) {
+            total += 10;
+        }
+        return total;
+    }
+
+    function compare(a, b) {
+        return a < b ? -1 : a > b ? 1 : 0;
+    }
+
+    function search(query) {
+        const terms = words(query);
+        if (terms.length === 0) {
+            return [];
+        }
+        const joined = terms.join(" ");
+        const name = (e) => e.label || e.curie;
+        return index
+            .map((e) => [score(e, terms, joined), e])
+            .filter(([s]) => s !== null)
+            .sort(
+                ([sa, a], [sb, b]) =>
+                    sb - sa ||
+                    Array.from(name(a)).length - Array.from(name(b)).length ||
+                    compare(name(a), name(b)) ||
+                    compare(a.iri, b.iri),
+            )
+            .slice(0, LIMIT)
+            .map(([, e]) => e);
+    }
+
+    function show(results, list) {
+        list.replaceChildren(
+            ...results.map((e) => {
+                const item = document.createElement("li");
+                const link = document.createElement("a");
+                link.href = e.url;
+                link.textContent = e.label || e.curie;
+                item.append(link);
+                if (e.label) {
+                    const curie = document.createElement("small");
+                    curie.textContent = " " + e.curie;
+                    item.append(curie);
+                }
+                return item;
+            }),
+        );
+        list.hidden = results.length === 0;
+    }
+
+    async function load() {
+        const response = await fetch(script.dataset.index);
+        const data = await response.json();
+        index = data.entries.map((row) =>
+            Object.fromEntries(data.fields.map((f, i) => [f, row[i]])),
+        );
+    }
+
+    document.addEventListener("DOMContentLoaded", () => {
+        const form = document.getElementById("search");
+        const input = document.getElementById("search-input");
+        const list = document.getElementById("search-results");
+        if (!form || !input || !list) {
+            return;
+        }
+        let loading = null;
+        input.addEventListener("input", async () => {
+            loading = loading || load();
+            await loading;
+            show(search(input.value), list);
+        });
+        form.addEventListener("submit", (event) => {
+            event.preventDefault();
+            const first = list.querySelector("a");
+            if (first) {
+                window.location.href = first.href;
+            }
+        });
+    });
+})();